@@ -32,10 +32,16 @@ pub trait AuctionMock: storage::AuctionMockStorage {
 
         let mut new_num_nodes = self.get_num_nodes();
         let expected_payment = BigUint::from(num_nodes as u64) * self.get_stake_per_node();
-        require!(
-            payment == expected_payment,
-            "incorrect payment to auction mock"
-        );
+        if num_nodes == 0 {
+            // top-up only
+            require!(payment > 0, "incorrect payment to auction mock");
+            self.set_top_up(&(self.get_top_up() + &payment));
+        } else {
+            require!(
+                payment == expected_payment,
+                "incorrect payment to auction mock"
+            );
+        }
 
         let mut result_err_data: MultiValueEncoded<ManagedBuffer> = MultiValueEncoded::new();
         for key_sig_pair in bls_keys_signatures.into_iter() {
@@ -125,8 +131,18 @@ pub trait AuctionMock: storage::AuctionMockStorage {
         self.unbond_endpoint(bls_keys)
     }
 
+    /// Takes the tokens out of the top-up. Same as the real auction contract, fails if the top-up is not enough.
     #[endpoint(unStakeTokens)]
-    fn unstake_tokens(&self, _amount: BigUint) {}
+    fn unstake_tokens(&self, amount: BigUint) {
+        require!(
+            !self.is_staking_failure(),
+            "auction smart contract deliberate error"
+        );
+
+        let top_up = self.get_top_up();
+        require!(amount <= top_up, "cannot unstake more than the top-up");
+        self.set_top_up(&(top_up - &amount));
+    }
 
     #[endpoint(unBondTokens)]
     fn unbond_tokens(&self, amount: BigUint) {
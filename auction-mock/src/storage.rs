@@ -25,6 +25,13 @@ pub trait AuctionMockStorage {
     #[storage_set("unBond_bls_key")]
     fn set_unbond_bls_key(&self, node_index: usize, bls_key: &[u8]);
 
+    #[view(getTopUp)]
+    #[storage_get("top_up")]
+    fn get_top_up(&self) -> BigUint;
+
+    #[storage_set("top_up")]
+    fn set_top_up(&self, top_up: &BigUint);
+
     #[storage_set("staking_failure")]
     fn set_staking_failure(&self, will_fail: bool);
 
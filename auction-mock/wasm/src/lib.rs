@@ -5,9 +5,9 @@
 ////////////////////////////////////////////////////
 
 // Init:                                 1
-// Endpoints:                           11
+// Endpoints:                           12
 // Async Callback (empty):               1
-// Total number of exported functions:  13
+// Total number of exported functions:  14
 
 #![no_std]
 #![feature(alloc_error_handler, lang_items)]
@@ -27,6 +27,7 @@ multiversx_sc_wasm_adapter::endpoints! {
         unBondTokens
         claim
         unJail
+        getTopUp
         setBlsDeliberateError
         getBlsDeliberateError
     )
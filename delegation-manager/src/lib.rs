@@ -0,0 +1,208 @@
+#![no_std]
+
+use delegation_latest::settings::ProxyTrait as _;
+use delegation_latest::user_fund_storage::fund_view_module::ProxyTrait as _;
+
+multiversx_sc::imports!();
+
+/// Deploys delegation contracts (`DelegationFull`) from a template and keeps track of them.
+///
+/// The template is an already deployed delegation contract, whose code is copied for each new pool.
+/// Each new pool is handed over to its owner right after deployment:
+/// the owner becomes both the contract owner and the owner user (the node reward destination).
+#[multiversx_sc::derive::contract]
+pub trait DelegationManager {
+    #[init]
+    fn init(&self, template_address: ManagedAddress) {
+        self.set_template_address_endpoint(template_address);
+    }
+
+    // TEMPLATE
+
+    /// The contract whose code gets copied for each new pool.
+    #[view(getTemplateAddress)]
+    #[storage_mapper("template_address")]
+    fn template_address(&self) -> SingleValueMapper<ManagedAddress>;
+
+    #[only_owner]
+    #[endpoint(setTemplateAddress)]
+    fn set_template_address_endpoint(&self, template_address: ManagedAddress) {
+        require!(
+            self.blockchain().is_smart_contract(&template_address),
+            "template must be a smart contract"
+        );
+        self.template_address().set(&template_address);
+    }
+
+    // POOLS
+
+    /// Deploys a new delegation pool from the template.
+    /// The arguments after the owner are the same as those of the delegation contract constructor.
+    /// Returns the address of the new pool.
+    #[only_owner]
+    #[endpoint(createPool)]
+    #[allow(clippy::too_many_arguments)]
+    fn create_pool(
+        &self,
+        pool_owner: ManagedAddress,
+        auction_contract_addr: ManagedAddress,
+        service_fee_per_10000: usize,
+        owner_min_stake_share_per_10000: usize,
+        n_blocks_before_unbond: u64,
+        minimum_stake: BigUint,
+        total_delegation_cap: BigUint,
+    ) -> ManagedAddress {
+        require!(
+            !self.template_address().is_empty(),
+            "template address not set"
+        );
+        require!(
+            !self.blockchain().is_smart_contract(&pool_owner),
+            "pool owner cannot be a smart contract"
+        );
+
+        let (pool_address, ()) = self
+            .pool_proxy(ManagedAddress::zero())
+            .init(
+                &auction_contract_addr,
+                service_fee_per_10000,
+                owner_min_stake_share_per_10000,
+                n_blocks_before_unbond,
+                minimum_stake,
+                total_delegation_cap,
+                OptionalValue::Some(pool_owner.clone()),
+            )
+            .deploy_from_source(
+                &self.template_address().get(),
+                CodeMetadata::UPGRADEABLE | CodeMetadata::READABLE,
+            );
+        require!(!pool_address.is_zero(), "pool deploy failed");
+
+        // the owner user was set on deploy, only the contract ownership is left to hand over
+        self.send()
+            .change_owner_address(pool_address.clone(), &pool_owner)
+            .execute_on_dest_context::<()>();
+
+        self.pools().push(&pool_address);
+        self.pool_owner(&pool_address).set(&pool_owner);
+        self.pool_created_event(&pool_address, &pool_owner);
+
+        pool_address
+    }
+
+    #[proxy]
+    fn pool_proxy(&self, sc_address: ManagedAddress) -> delegation_latest_full::Proxy<Self::Api>;
+
+    /// All pools created by the manager, in order of creation.
+    #[storage_mapper("pools")]
+    fn pools(&self) -> VecMapper<ManagedAddress>;
+
+    /// The owner the pool was handed over to when it was created.
+    #[view(getPoolOwner)]
+    #[storage_mapper("pool_owner")]
+    fn pool_owner(&self, pool_address: &ManagedAddress) -> SingleValueMapper<ManagedAddress>;
+
+    #[event("poolCreated")]
+    fn pool_created_event(
+        &self,
+        #[indexed] pool_address: &ManagedAddress,
+        #[indexed] pool_owner: &ManagedAddress,
+    );
+
+    // VIEWS
+
+    #[view(getNumPools)]
+    fn get_num_pools(&self) -> usize {
+        self.pools().len()
+    }
+
+    /// Lists a page of pools, with their owners, in order of creation.
+    /// Pages work the same as in `getAggregatedStake`:
+    /// yields the position where the next page starts (0 if there are no more pages), followed by the pools.
+    #[view(getAllPools)]
+    fn get_all_pools(
+        &self,
+        from: usize,
+        count: usize,
+    ) -> MultiValue2<usize, MultiValueEncoded<MultiValue2<ManagedAddress, ManagedAddress>>> {
+        let mut result = MultiValueEncoded::new();
+        let next_from = self.foreach_pool_page(from, count, |pool_address| {
+            let pool_owner = self.pool_owner(&pool_address).get();
+            result.push((pool_address, pool_owner).into());
+        });
+        (next_from, result).into()
+    }
+
+    /// Looks at a page of pools, same as `getAllPools`, and lists the ones of the given owner.
+    /// A page can come out empty even if there are more pages.
+    #[view(getPoolsOfOwner)]
+    fn get_pools_of_owner(
+        &self,
+        pool_owner: ManagedAddress,
+        from: usize,
+        count: usize,
+    ) -> MultiValue2<usize, MultiValueEncoded<ManagedAddress>> {
+        let mut result = MultiValueEncoded::new();
+        let next_from = self.foreach_pool_page(from, count, |pool_address| {
+            if self.pool_owner(&pool_address).get() == pool_owner {
+                result.push(pool_address);
+            }
+        });
+        (next_from, result).into()
+    }
+
+    /// Sums up the stake of a page of pools, as reported by each pool.
+    /// Pages start at a 0-based position in the pool list and contain at most `count` pools,
+    /// so that the number of calls to the pools stays bounded.
+    /// Yields the position where the next page starts (0 if there are no more pages), followed by:
+    /// - total delegation cap,
+    /// - total active stake,
+    /// - total waiting stake.
+    #[view(getAggregatedStake)]
+    fn get_aggregated_stake(
+        &self,
+        from: usize,
+        count: usize,
+    ) -> MultiValue4<usize, BigUint, BigUint, BigUint> {
+        let mut total_delegation_cap = BigUint::zero();
+        let mut total_active = BigUint::zero();
+        let mut total_waiting = BigUint::zero();
+        let next_from = self.foreach_pool_page(from, count, |pool_address| {
+            total_delegation_cap += self
+                .pool_proxy(pool_address.clone())
+                .get_total_delegation_cap()
+                .execute_on_dest_context::<BigUint>();
+            total_active += self
+                .pool_proxy(pool_address.clone())
+                .get_total_active_stake()
+                .execute_on_dest_context::<BigUint>();
+            total_waiting += self
+                .pool_proxy(pool_address)
+                .get_total_waiting_stake()
+                .execute_on_dest_context::<BigUint>();
+        });
+        (next_from, total_delegation_cap, total_active, total_waiting).into()
+    }
+
+    /// Calls `f` for the pools from 0-based position `from`, at most `count` of them.
+    /// Returns the position where the next page starts, 0 if there are no more pages.
+    fn foreach_pool_page<F: FnMut(ManagedAddress)>(
+        &self,
+        from: usize,
+        count: usize,
+        mut f: F,
+    ) -> usize {
+        let pools = self.pools();
+        let num_pools = pools.len();
+        let end = core::cmp::min(num_pools, from.saturating_add(count));
+        for index in from..end {
+            // VecMapper indexes start at 1
+            f(pools.get(index + 1));
+        }
+        if end < num_pools {
+            end
+        } else {
+            0
+        }
+    }
+}
@@ -0,0 +1,9 @@
+#[test]
+fn aggregated_views_go() {
+    multiversx_sc_scenario::run_go("scenarios/aggregated_views.scen.json");
+}
+
+#[test]
+fn create_pool_go() {
+    multiversx_sc_scenario::run_go("scenarios/create_pool.scen.json");
+}
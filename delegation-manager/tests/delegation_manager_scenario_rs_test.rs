@@ -0,0 +1,29 @@
+use multiversx_sc_scenario::*;
+
+fn world() -> ScenarioWorld {
+    let mut blockchain = ScenarioWorld::new();
+    blockchain.set_current_dir_from_workspace("delegation-manager");
+    blockchain.register_contract(
+        "file:../auction-mock/output/auction-mock.wasm",
+        auction_mock::ContractBuilder,
+    );
+    blockchain.register_contract(
+        "file:../latest_full/output/delegation_latest_full.wasm",
+        delegation_latest_full::ContractBuilder,
+    );
+    blockchain.register_contract(
+        "file:output/delegation-manager.wasm",
+        delegation_manager::ContractBuilder,
+    );
+    blockchain
+}
+
+#[test]
+fn aggregated_views_rs() {
+    multiversx_sc_scenario::run_rs("scenarios/aggregated_views.scen.json", world());
+}
+
+#[test]
+fn create_pool_rs() {
+    multiversx_sc_scenario::run_rs("scenarios/create_pool.scen.json", world());
+}
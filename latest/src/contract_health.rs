@@ -0,0 +1,223 @@
+use crate::settings::{OWNER_USER_ID, PERCENTAGE_DENOMINATOR};
+use node_storage::types::NodeState;
+use user_fund_storage::fund_view_module::USER_STAKE_TOTALS_ID;
+use user_fund_storage::types::FundType;
+
+multiversx_sc::imports!();
+multiversx_sc::derive_imports!();
+
+/// Number of nodes in each state.
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, TypeAbi, Default)]
+pub struct NodeStateCounts {
+    pub inactive: usize,
+    pub pending_activation: usize,
+    pub activation_failed: usize,
+    pub active: usize,
+    pub pending_deactivation: usize,
+    pub unbond_period: usize,
+    pub pending_unbond: usize,
+    pub removed: usize,
+}
+
+/// Result of all the contract invariant checks.
+/// Unlike the `validate...` views, nothing fails, each check is reported instead.
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, TypeAbi)]
+pub struct ContractHealth<M: ManagedTypeApi> {
+    /// Outside bootstrap mode, active + unstaked must equal the total delegation cap.
+    /// In bootstrap mode, they must be below it.
+    pub delegation_cap_invariant_ok: bool,
+    pub bootstrap_mode: bool,
+    pub total_delegation_cap: BigUint<M>,
+    pub total_active: BigUint<M>,
+    pub total_unstaked: BigUint<M>,
+
+    pub owner_stake_share_ok: bool,
+    pub owner_active_stake: BigUint<M>,
+    pub owner_min_stake_share: BigUint<M>,
+
+    // breakdown of what the contract must hold, each part is subtracted from the balance for `unprotected`
+    pub egld_balance: BigUint<M>,
+    pub total_waiting: BigUint<M>,
+    /// Unstaked stake waiting for the unbond period, paid out by `unBond`.
+    pub total_deferred_payment: BigUint<M>,
+    pub total_withdraw_only: BigUint<M>,
+    pub unclaimed_rewards: BigUint<M>,
+    /// Funds set aside for keeper bounties, not part of `unprotected`.
+    pub keeper_pool: BigUint<M>,
+    /// Whether the balance covers what the contract must hold:
+    /// waiting + deferred payment + withdraw only stake + unclaimed rewards + keeper pool.
+    pub solvent: bool,
+    /// Balance above what the contract must hold, i.e. `getTotalUnProtected`. Zero if not solvent.
+    pub unprotected: BigUint<M>,
+    /// Balance missing to cover what the contract must hold. Zero if solvent.
+    pub shortfall: BigUint<M>,
+
+    pub node_state_counts: NodeStateCounts,
+    pub global_op_in_progress: bool,
+}
+
+/// How the active stake is backed, top-up included.
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, TypeAbi)]
+pub struct TopUpReconciliation<M: ManagedTypeApi> {
+    pub auction_staked_tokens: BigUint<M>,
+    pub auction_top_up: BigUint<M>,
+    /// Staked tokens required by the nodes, i.e. the auction staked tokens without the top-up.
+    pub auction_node_stake: BigUint<M>,
+    pub auction_unstaked_tokens: BigUint<M>,
+    /// Same as in `getContractHealth`. Zero if not solvent.
+    pub unprotected: BigUint<M>,
+    pub total_active: BigUint<M>,
+    /// Whether auction staked + unstaked tokens + unprotected funds cover the active stake,
+    /// after covering any contract shortfall.
+    pub reconciled: bool,
+    pub surplus: BigUint<M>,
+    pub shortfall: BigUint<M>,
+}
+
+#[multiversx_sc::derive::module]
+pub trait ContractHealthModule:
+    node_storage::node_config::NodeConfigModule
+    + crate::settings::SettingsModule
+    + crate::rewards_state::RewardStateModule
+    + crate::reset_checkpoint_state::ResetCheckpointStateModule
+    + crate::liquid_staking_state::LiquidStakingStateModule
+    + crate::user_stake_state::UserStakeStateModule
+    + crate::node_activation::NodeActivationModule
+    + crate::events::EventsModule
+    + crate::keeper_bounty::KeeperBountyModule
+    + crate::roles::RolesModule
+    + user_fund_storage::user_data::UserDataModule
+    + user_fund_storage::fund_module::FundModule
+    + user_fund_storage::fund_view_module::FundViewModule
+    + user_fund_storage::fund_transf_module::FundTransformationsModule
+    + multiversx_sc_modules::pause::PauseModule
+{
+    /// Runs all invariant checks and reports the results, for monitoring.
+    #[view(getContractHealth)]
+    fn get_contract_health(&self) -> ContractHealth<Self::Api> {
+        // delegation cap invariant, same as validateDelegationCapInvariant
+        let bootstrap_mode = self.is_bootstrap_mode();
+        let total_delegation_cap = self.get_total_delegation_cap();
+        let total_active = self.get_user_stake_of_type(USER_STAKE_TOTALS_ID, FundType::Active);
+        let total_unstaked = self.get_user_stake_of_type(USER_STAKE_TOTALS_ID, FundType::UnStaked);
+        let active_and_unstaked = &total_active + &total_unstaked;
+        let delegation_cap_invariant_ok = if bootstrap_mode {
+            active_and_unstaked < total_delegation_cap
+        } else {
+            active_and_unstaked == total_delegation_cap
+        };
+
+        // owner stake share, same as validateOwnerStakeShare
+        let owner_active_stake = self.get_user_stake_of_type(OWNER_USER_ID.get(), FundType::Active);
+        let owner_min_stake_share = self.get_owner_min_stake_share();
+        let owner_stake_share_ok = &owner_active_stake * &BigUint::from(PERCENTAGE_DENOMINATOR)
+            >= &total_active * &owner_min_stake_share;
+
+        // solvency, same computation as getTotalUnProtected, but without failing
+        let egld_balance = self
+            .blockchain()
+            .get_sc_balance(&EgldOrEsdtTokenIdentifier::egld(), 0);
+        let total_waiting = self.get_user_stake_of_type(USER_STAKE_TOTALS_ID, FundType::Waiting);
+        let total_deferred_payment =
+            self.get_user_stake_of_type(USER_STAKE_TOTALS_ID, FundType::DeferredPayment);
+        let total_withdraw_only =
+            self.get_user_stake_of_type(USER_STAKE_TOTALS_ID, FundType::WithdrawOnly);
+        let total_rewards = self.get_total_cumulated_rewards();
+        let sent_rewards = self.get_sent_rewards();
+        let unclaimed_rewards = if total_rewards > sent_rewards {
+            &total_rewards - &sent_rewards
+        } else {
+            BigUint::zero()
+        };
+        let keeper_pool = self.keeper_pool().get();
+        let available = &egld_balance + &sent_rewards;
+        let required = &total_rewards
+            + &total_waiting
+            + &total_deferred_payment
+            + &total_withdraw_only
+            + &keeper_pool;
+        let solvent = available >= required;
+        let (unprotected, shortfall) = if solvent {
+            (&available - &required, BigUint::zero())
+        } else {
+            (BigUint::zero(), &required - &available)
+        };
+
+        ContractHealth {
+            delegation_cap_invariant_ok,
+            bootstrap_mode,
+            total_delegation_cap,
+            total_active,
+            total_unstaked,
+            owner_stake_share_ok,
+            owner_active_stake,
+            owner_min_stake_share,
+            egld_balance,
+            total_waiting,
+            total_deferred_payment,
+            total_withdraw_only,
+            unclaimed_rewards,
+            keeper_pool,
+            solvent,
+            unprotected,
+            shortfall,
+            node_state_counts: self.count_node_states(),
+            global_op_in_progress: self.is_global_op_in_progress(),
+        }
+    }
+
+    /// Reconciles the tokens tracked in the auction contract, top-up included,
+    /// against the active stake and `getTotalUnProtected`.
+    /// Active stake is backed either by tokens in the auction contract, or by unprotected funds.
+    #[view(getTopUpReconciliation)]
+    fn get_top_up_reconciliation(&self) -> TopUpReconciliation<Self::Api> {
+        let health = self.get_contract_health();
+        let auction_staked_tokens = self.auction_staked_tokens().get();
+        let auction_top_up = self.auction_top_up().get();
+        let auction_node_stake = if auction_staked_tokens > auction_top_up {
+            &auction_staked_tokens - &auction_top_up
+        } else {
+            BigUint::zero()
+        };
+        let auction_unstaked_tokens = self.auction_unstaked_tokens().get();
+
+        let backing = &auction_staked_tokens + &auction_unstaked_tokens + &health.unprotected;
+        let required = &health.total_active + &health.shortfall;
+        let reconciled = backing >= required;
+        let (surplus, shortfall) = if reconciled {
+            (&backing - &required, BigUint::zero())
+        } else {
+            (BigUint::zero(), &required - &backing)
+        };
+
+        TopUpReconciliation {
+            auction_staked_tokens,
+            auction_top_up,
+            auction_node_stake,
+            auction_unstaked_tokens,
+            unprotected: health.unprotected,
+            total_active: health.total_active,
+            reconciled,
+            surplus,
+            shortfall,
+        }
+    }
+
+    fn count_node_states(&self) -> NodeStateCounts {
+        let mut counts = NodeStateCounts::default();
+        let num_nodes = self.num_nodes().get();
+        for node_id in 1..=num_nodes {
+            match self.get_node_state(node_id) {
+                NodeState::Inactive => counts.inactive += 1,
+                NodeState::PendingActivation => counts.pending_activation += 1,
+                NodeState::ActivationFailed => counts.activation_failed += 1,
+                NodeState::Active => counts.active += 1,
+                NodeState::PendingDeactivation => counts.pending_deactivation += 1,
+                NodeState::UnBondPeriod { .. } => counts.unbond_period += 1,
+                NodeState::PendingUnBond { .. } => counts.pending_unbond += 1,
+                NodeState::Removed => counts.removed += 1,
+            }
+        }
+        counts
+    }
+}
@@ -0,0 +1,223 @@
+use crate::settings::{WaitingActivationPolicy, OWNER_USER_ID};
+use core::num::NonZeroUsize;
+use user_fund_storage::fund_module::SwapDirection;
+use user_fund_storage::types::{FundDescription, FundItem, FundType};
+
+multiversx_sc::imports!();
+multiversx_sc::derive_imports!();
+
+/// How far down the waiting list `getDelegatorInfo` looks for the first waiting entry of the delegator.
+pub const DELEGATOR_INFO_MAX_WAITING_ENTRIES: usize = 100;
+
+/// A deferred payment entry, together with the block nonce from which it can be unbonded.
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, ManagedVecItem, TypeAbi)]
+pub struct DeferredPaymentInfo<M: ManagedTypeApi> {
+    pub amount: BigUint<M>,
+    pub created: u64,
+    pub unbond_block_nonce: u64,
+}
+
+/// Everything a delegator dashboard needs, read in one go, so that all values are consistent with each other.
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, TypeAbi)]
+pub struct DelegatorInfo<M: ManagedTypeApi> {
+    /// 0 if the address is not a delegator.
+    pub user_id: usize,
+    pub withdraw_only_stake: BigUint<M>,
+    pub waiting_stake: BigUint<M>,
+    pub active_stake: BigUint<M>,
+    pub unstaked_stake: BigUint<M>,
+    pub deferred_payment_stake: BigUint<M>,
+    pub claimable_rewards: BigUint<M>,
+    /// What `unBond` would yield right now.
+    pub unbondable: BigUint<M>,
+    pub deferred_payments: ManagedVec<M, DeferredPaymentInfo<M>>,
+    /// 1-based position of the first waiting entry of the delegator in activation order,
+    /// 0 if the delegator has no waiting stake, or if the activation order is not known in advance
+    /// (largest first and pro rata policies).
+    pub waiting_list_position: usize,
+    /// Sum of the waiting entries activated before the first waiting entry of the delegator.
+    pub waiting_stake_ahead: BigUint<M>,
+    /// True if the first waiting entry of the delegator is further down the waiting list
+    /// than `DELEGATOR_INFO_MAX_WAITING_ENTRIES`. The position and the stake ahead are 0 in that case.
+    pub waiting_list_truncated: bool,
+}
+
+#[multiversx_sc::derive::module]
+pub trait DelegatorInfoModule:
+    crate::settings::SettingsModule
+    + crate::rewards_state::RewardStateModule
+    + crate::events::EventsModule
+    + user_fund_storage::user_data::UserDataModule
+    + user_fund_storage::fund_module::FundModule
+    + user_fund_storage::fund_view_module::FundViewModule
+    + user_fund_storage::fund_transf_module::FundTransformationsModule
+{
+    #[view(getDelegatorInfo)]
+    fn get_delegator_info(&self, user_address: ManagedAddress) -> DelegatorInfo<Self::Api> {
+        let user_id = self.get_user_id(&user_address);
+        let mut info = DelegatorInfo {
+            user_id,
+            withdraw_only_stake: BigUint::zero(),
+            waiting_stake: BigUint::zero(),
+            active_stake: BigUint::zero(),
+            unstaked_stake: BigUint::zero(),
+            deferred_payment_stake: BigUint::zero(),
+            claimable_rewards: BigUint::zero(),
+            unbondable: BigUint::zero(),
+            deferred_payments: ManagedVec::new(),
+            waiting_list_position: 0,
+            waiting_stake_ahead: BigUint::zero(),
+            waiting_list_truncated: false,
+        };
+        let user_id_nz = if let Some(user_id_nz) = NonZeroUsize::new(user_id) {
+            user_id_nz
+        } else {
+            return info;
+        };
+
+        info.withdraw_only_stake = self.get_user_stake_of_type(user_id, FundType::WithdrawOnly);
+        info.waiting_stake = self.get_user_stake_of_type(user_id, FundType::Waiting);
+        info.active_stake = self.get_user_stake_of_type(user_id, FundType::Active);
+        info.unstaked_stake = self.get_user_stake_of_type(user_id, FundType::UnStaked);
+        info.deferred_payment_stake =
+            self.get_user_stake_of_type(user_id, FundType::DeferredPayment);
+        info.claimable_rewards = self.load_updated_user_rewards(user_id_nz).unclaimed_rewards;
+
+        let current_bl_nonce = self.blockchain().get_block_nonce();
+        let n_blocks_before_unbond = self.get_n_blocks_before_unbond();
+        info.unbondable = info.withdraw_only_stake.clone();
+        self.foreach_fund_by_user_type(
+            user_id,
+            FundType::DeferredPayment,
+            SwapDirection::Forwards,
+            |fund_item| {
+                if let FundDescription::DeferredPayment { created } = fund_item.fund_desc {
+                    let unbond_block_nonce = created + n_blocks_before_unbond;
+                    if current_bl_nonce >= unbond_block_nonce {
+                        info.unbondable += &fund_item.balance;
+                    }
+                    info.deferred_payments.push(DeferredPaymentInfo {
+                        amount: fund_item.balance,
+                        created,
+                        unbond_block_nonce,
+                    });
+                }
+            },
+        );
+
+        if info.waiting_stake > 0 {
+            let (position, stake_ahead, truncated) =
+                self.get_waiting_stake_ahead(user_id, DELEGATOR_INFO_MAX_WAITING_ENTRIES);
+            info.waiting_list_position = position;
+            info.waiting_stake_ahead = stake_ahead;
+            info.waiting_list_truncated = truncated;
+        }
+
+        info
+    }
+
+    /// Lists the waiting entries of a delegator, in activation order, each with:
+    /// - the amount,
+    /// - the block nonce when it was created,
+    /// - the waiting stake activated before it,
+    /// - how much new delegation cap or unstaked stake is needed before it becomes fully active.
+    ///
+    /// Only the first `limit` entries of the waiting list are looked at,
+    /// so that the cost does not grow with the waiting list. Entries of the delegator further back are not listed.
+    ///
+    /// Under the first in first out and owner first policies, waiting stake is activated as soon as
+    /// there is room for it, so the room needed is the stake ahead plus the entry itself.
+    /// Under the largest first and pro rata policies the order depends on the other entries
+    /// at the time of activation, so nothing is returned.
+    #[view(getWaitingQueuePosition)]
+    fn get_waiting_queue_position(
+        &self,
+        user_address: ManagedAddress,
+        limit: usize,
+    ) -> MultiValueEncoded<MultiValue4<BigUint, u64, BigUint, BigUint>> {
+        let mut result = MultiValueEncoded::new();
+        let user_id = self.get_user_id(&user_address);
+        if user_id == 0 || self.get_user_stake_of_type(user_id, FundType::Waiting) == 0 {
+            return result;
+        }
+
+        let _ = self.foreach_waiting_entry_of_user(user_id, limit, |_, fund_item, stake_ahead| {
+            if let FundDescription::Waiting { created } = fund_item.fund_desc {
+                let needed_before_active = stake_ahead + &fund_item.balance;
+                result.push(
+                    (
+                        fund_item.balance.clone(),
+                        created,
+                        stake_ahead.clone(),
+                        needed_before_active,
+                    )
+                        .into(),
+                );
+            }
+            true
+        });
+        result
+    }
+
+    /// Walks the waiting list in activation order, up to the first entry of the given user,
+    /// looking at no more than `max_entries` entries of the list.
+    /// Yields the 1-based position of that entry, and the sum of all entries before it.
+    /// Yields 0 for both under the largest first and pro rata policies, where the order is not known in advance,
+    /// and when the entry lies further down than `max_entries`, in which case the last value is true.
+    fn get_waiting_stake_ahead(
+        &self,
+        user_id: usize,
+        max_entries: usize,
+    ) -> (usize, BigUint, bool) {
+        let mut result = (0usize, BigUint::zero());
+        let truncated =
+            self.foreach_waiting_entry_of_user(user_id, max_entries, |position, _, stake_ahead| {
+                result = (position, stake_ahead.clone());
+                false
+            });
+        (result.0, result.1, truncated)
+    }
+
+    /// Walks the waiting list in activation order, looking at no more than `max_entries` entries of the list.
+    /// Calls `f` for each entry of the given user, with its 1-based position and the sum of all entries before it.
+    /// Stops as soon as `f` returns false.
+    /// Returns true if the walk stopped at `max_entries`, with entries left to look at.
+    /// Does nothing under the largest first and pro rata policies, where the order is not known in advance.
+    fn foreach_waiting_entry_of_user<F>(&self, user_id: usize, max_entries: usize, mut f: F) -> bool
+    where
+        F: FnMut(usize, &FundItem<Self::Api>, &BigUint) -> bool,
+    {
+        let owner_first = match self.waiting_activation_policy().get() {
+            WaitingActivationPolicy::FirstInFirstOut => false,
+            WaitingActivationPolicy::OwnerFirst => true,
+            WaitingActivationPolicy::LargestFirst | WaitingActivationPolicy::ProRata => {
+                return false;
+            }
+        };
+
+        // under the owner first policy, owner entries are all ahead of the others
+        let owner_id = OWNER_USER_ID.get();
+        let mut position = 0usize;
+        let mut stake_ahead = BigUint::zero();
+        if owner_first && user_id != owner_id {
+            position = self.count_fund_items_by_user_type(owner_id, FundType::Waiting, |_| true);
+            stake_ahead = self.get_user_stake_of_type(owner_id, FundType::Waiting);
+        }
+        let mut id = self.get_fund_list_by_type(FundType::Waiting).first;
+        let mut num_seen = 0usize;
+        while id > 0 && num_seen < max_entries {
+            num_seen += 1;
+            let fund_item = self.fund_by_id(id).get();
+            id = fund_item.type_list_next;
+            if owner_first && (fund_item.user_id == owner_id) != (user_id == owner_id) {
+                continue;
+            }
+            position += 1;
+            if fund_item.user_id == user_id && !f(position, &fund_item, &stake_ahead) {
+                return false;
+            }
+            stake_ahead += &fund_item.balance;
+        }
+        id > 0
+    }
+}
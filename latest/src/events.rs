@@ -1,4 +1,29 @@
+use crate::roles::Role;
+
 multiversx_sc::imports!();
+multiversx_sc::derive_imports!();
+
+/// Event data of `userWrapActiveStake`.
+#[derive(TopEncode, TopDecode, TypeAbi)]
+pub struct WrapActiveStakeData<M: ManagedTypeApi> {
+    pub stake_amount: BigUint<M>,
+    pub token_amount: BigUint<M>,
+}
+
+/// Event data of `userUnwrapActiveStake`.
+#[derive(TopEncode, TopDecode, TypeAbi)]
+pub struct UnwrapActiveStakeData<M: ManagedTypeApi> {
+    pub token_amount: BigUint<M>,
+    pub stake_amount: BigUint<M>,
+    pub reward_amount: BigUint<M>,
+}
+
+/// Event data of `topUpStakeOk` and `topUpUnstakeOk`.
+#[derive(TopEncode, TopDecode, TypeAbi)]
+pub struct TopUpChangeData<M: ManagedTypeApi> {
+    pub amount: BigUint<M>,
+    pub total_top_up: BigUint<M>,
+}
 
 /// Contains all events that can be emitted by the contract.
 #[multiversx_sc::derive::module]
@@ -9,6 +34,34 @@ pub trait EventsModule {
     #[event("userUnstake")]
     fn unstake_event(&self, #[indexed] delegator: &ManagedAddress, amount: &BigUint);
 
+    #[event("userWithdrawWaiting")]
+    fn withdraw_waiting_event(&self, #[indexed] delegator: &ManagedAddress, amount: &BigUint);
+
+    #[event("userUnBond")]
+    fn unbond_event(&self, #[indexed] delegator: &ManagedAddress, amount: &BigUint);
+
+    #[event("userTransferActiveStake")]
+    fn transfer_active_stake_event(
+        &self,
+        #[indexed] from: &ManagedAddress,
+        #[indexed] to: &ManagedAddress,
+        amount: &BigUint,
+    );
+
+    #[event("userWrapActiveStake")]
+    fn wrap_active_stake_event(
+        &self,
+        #[indexed] user: &ManagedAddress,
+        data: &WrapActiveStakeData<Self::Api>,
+    );
+
+    #[event("userUnwrapActiveStake")]
+    fn unwrap_active_stake_event(
+        &self,
+        #[indexed] user: &ManagedAddress,
+        data: &UnwrapActiveStakeData<Self::Api>,
+    );
+
     #[event("nodeStakeOk")]
     fn stake_node_ok_event(&self);
 
@@ -33,6 +86,79 @@ pub trait EventsModule {
     #[event("tokensUnbond")]
     fn unbond_tokens_event(&self, amount: &BigUint);
 
+    #[event("tokensUnstakeOk")]
+    fn unstake_tokens_ok_event(&self, amount: &BigUint);
+
+    #[event("tokensUnstakeFail")]
+    fn unstake_tokens_fail_event(&self, reason: &ManagedBuffer);
+
+    #[event("tokensUnbondOk")]
+    fn unbond_tokens_ok_event(&self, amount: &BigUint);
+
+    #[event("tokensUnbondFail")]
+    fn unbond_tokens_fail_event(&self, reason: &ManagedBuffer);
+
+    #[event("topUpStake")]
+    fn stake_top_up_event(&self, amount: &BigUint);
+
+    #[event("topUpStakeOk")]
+    fn stake_top_up_ok_event(&self, data: &TopUpChangeData<Self::Api>);
+
+    #[event("topUpStakeFail")]
+    fn stake_top_up_fail_event(&self, reason: &ManagedBuffer);
+
+    #[event("topUpUnstake")]
+    fn unstake_top_up_event(&self, amount: &BigUint);
+
+    #[event("topUpUnstakeOk")]
+    fn unstake_top_up_ok_event(&self, data: &TopUpChangeData<Self::Api>);
+
+    #[event("topUpUnstakeFail")]
+    fn unstake_top_up_fail_event(&self, reason: &ManagedBuffer);
+
     #[event("userClaimRewards")]
     fn claim_rewards_event(&self, #[indexed] user: &ManagedAddress, amount: &BigUint);
+
+    #[event("userClaimRewardsTo")]
+    fn claim_rewards_to_event(
+        &self,
+        #[indexed] user: &ManagedAddress,
+        #[indexed] destination: &ManagedAddress,
+        amount: &BigUint,
+    );
+
+    #[event("userReDelegateRewards")]
+    fn redelegate_rewards_event(&self, #[indexed] user: &ManagedAddress, amount: &BigUint);
+
+    #[event("userAutoCompoundRewards")]
+    fn auto_compound_rewards_event(&self, #[indexed] user: &ManagedAddress, amount: &BigUint);
+
+    #[event("globalOpStepCompleted")]
+    fn global_op_step_completed_event(
+        &self,
+        #[indexed] operation: &ManagedBuffer,
+        #[indexed] step: &ManagedBuffer,
+    );
+
+    #[event("globalOpAborted")]
+    fn global_op_aborted_event(&self, #[indexed] operation: &ManagedBuffer);
+
+    #[event("keeperBountyPaid")]
+    fn keeper_bounty_paid_event(&self, #[indexed] keeper: &ManagedAddress, amount: &BigUint);
+
+    #[event("serviceFeeAnnounced")]
+    fn service_fee_announced_event(
+        &self,
+        #[indexed] service_fee: usize,
+        #[indexed] effective_block_nonce: u64,
+    );
+
+    #[event("serviceFeeAnnouncementCancelled")]
+    fn service_fee_announcement_cancelled_event(&self, #[indexed] service_fee: usize);
+
+    #[event("roleGranted")]
+    fn grant_role_event(&self, #[indexed] role: &Role, #[indexed] address: &ManagedAddress);
+
+    #[event("roleRevoked")]
+    fn revoke_role_event(&self, #[indexed] role: &Role, #[indexed] address: &ManagedAddress);
 }
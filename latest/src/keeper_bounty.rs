@@ -0,0 +1,132 @@
+use crate::settings::PERCENTAGE_DENOMINATOR;
+
+multiversx_sc::imports!();
+
+/// Pays a fixed bounty to whoever drives long-running operations forward:
+/// `continueGlobalOperation`, `unBondAllPossibleNodes` and the dust cleanup endpoints.
+/// The latter are open to addresses with the keeper role, not only to node operators.
+/// The bounty comes out of the keeper pool, funded by the owner or by a share of the service fee.
+#[multiversx_sc::derive::module]
+pub trait KeeperBountyModule:
+    crate::settings::SettingsModule
+    + crate::rewards_state::RewardStateModule
+    + crate::events::EventsModule
+    + user_fund_storage::user_data::UserDataModule
+    + user_fund_storage::fund_module::FundModule
+    + user_fund_storage::fund_view_module::FundViewModule
+    + user_fund_storage::fund_transf_module::FundTransformationsModule
+{
+    /// Includes the share of the service fee from rewards that arrived since the reward index was last updated.
+    #[view(getKeeperPool)]
+    fn get_keeper_pool(&self) -> BigUint {
+        self.keeper_pool().get() + self.load_updated_reward_index().keeper_new_rewards
+    }
+
+    /// Amount paid for each call that makes progress. Zero means no bounty.
+    #[view(getKeeperBounty)]
+    #[storage_mapper("keeper_bounty")]
+    fn keeper_bounty(&self) -> SingleValueMapper<BigUint>;
+
+    /// Minimum number of blocks between 2 bounties for the same step of the same operation, whoever the keepers are.
+    /// At most one bounty is paid per block and step, even if zero.
+    #[view(getKeeperMinBlocksBetweenBounties)]
+    #[storage_mapper("keeper_min_blocks")]
+    fn keeper_min_blocks_between_bounties(&self) -> SingleValueMapper<u64>;
+
+    /// First block in which a bounty can be paid again for progress on the given operation step.
+    /// Global operations and their steps are named as in `getGlobalOperationProgress`,
+    /// the other operations are named after their endpoint, with an empty step.
+    #[view(getKeeperNextBountyBlock)]
+    #[storage_mapper("keeper_next_bounty_block")]
+    fn keeper_next_bounty_block(
+        &self,
+        operation: &ManagedBuffer,
+        step: &ManagedBuffer,
+    ) -> SingleValueMapper<u64>;
+
+    /// Keepers that are not node operators can only clean up dust below this limit. Zero by default.
+    #[view(getKeeperDustLimit)]
+    #[storage_mapper("keeper_dust_limit")]
+    fn keeper_dust_limit(&self) -> SingleValueMapper<BigUint>;
+
+    #[only_owner]
+    #[endpoint(setKeeperBounty)]
+    fn set_keeper_bounty(&self, bounty: BigUint, min_blocks_between_bounties: u64) {
+        self.keeper_bounty().set(&bounty);
+        self.keeper_min_blocks_between_bounties()
+            .set(min_blocks_between_bounties);
+    }
+
+    /// Rewards that arrived so far are split with the previous share.
+    #[only_owner]
+    #[endpoint(setKeeperServiceFeeShare)]
+    fn set_keeper_service_fee_share(&self, share_per_10000: usize) {
+        require!(
+            share_per_10000 <= PERCENTAGE_DENOMINATOR,
+            "keeper service fee share out of range"
+        );
+
+        self.update_reward_index();
+        self.keeper_service_fee_share().set(share_per_10000);
+    }
+
+    #[only_owner]
+    #[endpoint(setKeeperDustLimit)]
+    fn set_keeper_dust_limit(&self, dust_limit: BigUint) {
+        self.keeper_dust_limit().set(&dust_limit);
+    }
+
+    #[only_owner]
+    #[payable("EGLD")]
+    #[endpoint(fundKeeperPool)]
+    fn fund_keeper_pool(&self, #[payment] payment: BigUint) {
+        require!(payment > 0, "no funds sent");
+        self.keeper_pool()
+            .update(|keeper_pool| *keeper_pool += &payment);
+    }
+
+    #[only_owner]
+    #[endpoint(withdrawKeeperPool)]
+    fn withdraw_keeper_pool(&self, amount: BigUint) {
+        let keeper_pool = self.keeper_pool().get();
+        require!(amount <= keeper_pool, "not enough funds in keeper pool");
+        self.keeper_pool().set(&(keeper_pool - &amount));
+
+        let caller = self.blockchain().get_caller();
+        self.send().direct_egld(&caller, &amount);
+    }
+
+    /// Called by the endpoints that made progress on the given operation step.
+    /// Nothing gets paid if no bounty is configured, if the pool cannot cover it,
+    /// or if the rate limit of the step was hit. The call itself succeeds regardless.
+    fn pay_keeper_bounty(&self, operation: &[u8], step: &[u8]) {
+        let caller = self.blockchain().get_caller();
+        self.pay_keeper_bounty_to(&caller, operation, step);
+    }
+
+    /// Same as `pay_keeper_bounty`, for callbacks, where the caller is no longer the keeper.
+    fn pay_keeper_bounty_to(&self, keeper: &ManagedAddress, operation: &[u8], step: &[u8]) {
+        let bounty = self.keeper_bounty().get();
+        if bounty == 0 {
+            return;
+        }
+
+        let current_block = self.blockchain().get_block_nonce();
+        let next_bounty_block_mapper = self
+            .keeper_next_bounty_block(&ManagedBuffer::from(operation), &ManagedBuffer::from(step));
+        if current_block < next_bounty_block_mapper.get() {
+            return;
+        }
+
+        let keeper_pool = self.keeper_pool().get();
+        if keeper_pool < bounty {
+            return;
+        }
+        self.keeper_pool().set(&(keeper_pool - &bounty));
+        let min_blocks = core::cmp::max(self.keeper_min_blocks_between_bounties().get(), 1);
+        next_bounty_block_mapper.set(current_block + min_blocks);
+
+        self.send().direct_egld(keeper, &bounty);
+        self.keeper_bounty_paid_event(keeper, &bounty);
+    }
+}
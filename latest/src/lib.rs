@@ -6,13 +6,19 @@
 pub mod auction_proxy;
 
 // modules
+pub mod contract_health;
+pub mod delegator_info;
 pub mod events;
+pub mod keeper_bounty;
+pub mod liquid_staking_endpoints;
+pub mod liquid_staking_state;
 pub mod node_activation;
 pub mod reset_checkpoint_endpoints;
 pub mod reset_checkpoint_state;
 pub mod reset_checkpoint_types;
 pub mod rewards_endpoints;
 pub mod rewards_state;
+pub mod roles;
 pub mod settings;
 pub mod user_stake_dust_cleanup;
 pub mod user_stake_endpoints;
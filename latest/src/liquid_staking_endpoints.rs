@@ -0,0 +1,206 @@
+use core::num::NonZeroUsize;
+use user_fund_storage::types::FundType;
+
+multiversx_sc::imports!();
+
+pub const LIQUID_TOKEN_NUM_DECIMALS: usize = 18;
+
+/// Setup of the liquid staking token and the transfer-in path that turns tokens back into active stake.
+/// The tokenization itself is described in `LiquidStakingStateModule`.
+///
+/// The module does nothing until the owner issues the token and gives the contract the local mint/burn roles.
+#[multiversx_sc::derive::module]
+pub trait LiquidStakingEndpointsModule:
+    crate::settings::SettingsModule
+    + crate::rewards_state::RewardStateModule
+    + crate::reset_checkpoint_state::ResetCheckpointStateModule
+    + crate::liquid_staking_state::LiquidStakingStateModule
+    + crate::user_stake_state::UserStakeStateModule
+    + crate::events::EventsModule
+    + user_fund_storage::user_data::UserDataModule
+    + user_fund_storage::fund_module::FundModule
+    + user_fund_storage::fund_view_module::FundViewModule
+    + user_fund_storage::fund_transf_module::FundTransformationsModule
+    + multiversx_sc_modules::pause::PauseModule
+{
+    // TOKEN SETUP
+
+    /// Issues the liquid staking token. The EGLD payment is the issue cost.
+    /// The token id is saved in the callback, if the issue was successful.
+    #[only_owner]
+    #[payable("EGLD")]
+    #[endpoint(issueLiquidStakingToken)]
+    fn issue_liquid_staking_token(
+        &self,
+        token_display_name: ManagedBuffer,
+        token_ticker: ManagedBuffer,
+    ) {
+        require!(
+            self.liquid_token_id().is_empty(),
+            "liquid staking token already issued"
+        );
+
+        let issue_cost = self.call_value().egld_value();
+        let caller = self.blockchain().get_caller();
+
+        self.send()
+            .esdt_system_sc_proxy()
+            .issue_fungible(
+                issue_cost,
+                &token_display_name,
+                &token_ticker,
+                &BigUint::zero(),
+                FungibleTokenProperties {
+                    num_decimals: LIQUID_TOKEN_NUM_DECIMALS,
+                    can_freeze: false,
+                    can_wipe: false,
+                    can_pause: false,
+                    can_mint: true,
+                    can_burn: true,
+                    can_change_owner: false,
+                    can_upgrade: true,
+                    can_add_special_roles: true,
+                },
+            )
+            .async_call()
+            .with_callback(self.callbacks().liquid_token_issue_callback(&caller))
+            .call_and_exit()
+    }
+
+    #[callback]
+    fn liquid_token_issue_callback(
+        &self,
+        caller: &ManagedAddress,
+        #[call_result] result: ManagedAsyncCallResult<TokenIdentifier>,
+    ) {
+        match result {
+            ManagedAsyncCallResult::Ok(token_id) => {
+                self.liquid_token_id().set(&token_id);
+            }
+            ManagedAsyncCallResult::Err(_) => {
+                // return the issue cost to the owner
+                let refund = self.call_value().egld_value();
+                if refund > 0 {
+                    self.send().direct_egld(caller, &refund);
+                }
+            }
+        }
+    }
+
+    /// Gives the contract the local mint and burn roles for the liquid staking token.
+    /// Stake of delegators who opted in is tokenized from the moment the roles are in place.
+    #[only_owner]
+    #[endpoint(setLiquidStakingTokenRoles)]
+    fn set_liquid_staking_token_roles(&self) {
+        require!(
+            !self.liquid_token_id().is_empty(),
+            "liquid staking token not issued"
+        );
+
+        let roles = [EsdtLocalRole::Mint, EsdtLocalRole::Burn];
+        self.send()
+            .esdt_system_sc_proxy()
+            .set_special_roles(
+                &self.blockchain().get_sc_address(),
+                &self.liquid_token_id().get(),
+                roles.iter().cloned(),
+            )
+            .async_call()
+            .call_and_exit()
+    }
+
+    // ENDPOINTS
+
+    /// Delegators can opt in to receive liquid staking tokens for their stake as it becomes active.
+    /// Stake that is already active is not affected. Opting out drops the stake pending tokenization.
+    /// Can be called before the first stake, since in bootstrap mode stake can become active right away.
+    #[endpoint(setLiquidStaking)]
+    fn set_liquid_staking_endpoint(&self, enabled: bool) {
+        require!(self.not_paused(), "contract paused");
+
+        let caller = self.blockchain().get_caller();
+        if !enabled {
+            let user_id = self.get_user_id(&caller);
+            if user_id > 0 {
+                self.user_liquid_staking(user_id).clear();
+                self.user_liquid_pending(user_id).clear();
+            }
+            return;
+        }
+
+        require!(self.can_stake(&caller), "caller not allowed to stake");
+        let user_id = self.get_or_create_user(&caller);
+        self.user_liquid_staking(user_id).set(true);
+    }
+
+    #[view(isLiquidStakingEnabled)]
+    fn is_liquid_staking_enabled(&self, user_address: ManagedAddress) -> bool {
+        let user_id = self.get_user_id(&user_address);
+        user_id > 0 && self.user_liquid_staking(user_id).get()
+    }
+
+    /// Stake activated since opting in and not yet tokenized.
+    #[view(getPendingLiquidStake)]
+    fn get_pending_liquid_stake(&self, user_address: ManagedAddress) -> BigUint {
+        let user_id = self.get_user_id(&user_address);
+        if user_id == 0 {
+            return BigUint::zero();
+        }
+        self.user_liquid_pending(user_id).get()
+    }
+
+    /// Tokenizes the stake activated since opting in: it moves to the liquid staking pool
+    /// and the caller receives tokens at the current pool rate.
+    /// Stake that was unstaked or transferred in the meantime is no longer tokenized.
+    #[endpoint(claimLiquidStakingTokens)]
+    fn claim_liquid_staking_tokens(&self) {
+        require!(self.not_paused(), "contract paused");
+        require!(
+            !self.is_global_op_in_progress(),
+            "liquid staking is temporarily paused as checkpoint is reset"
+        );
+        require!(self.is_liquid_staking_active(), "liquid staking not active");
+
+        let caller = self.blockchain().get_caller();
+        let user_id = self.get_user_id(&caller);
+        require!(
+            user_id > 0 && self.is_liquid_staking_eligible(user_id),
+            "liquid staking not enabled"
+        );
+
+        let pending = self.user_liquid_pending(user_id).take();
+        let active = self.get_user_stake_of_type(user_id, FundType::Active);
+        let amount = core::cmp::min(pending, active);
+        require!(amount > 0, "no stake to tokenize");
+
+        self.tokenize_active_stake(user_id, &amount);
+    }
+
+    /// Burns the liquid staking tokens sent and gives the caller their share of the pool:
+    /// the active stake is transferred to the caller, who becomes a delegator if they weren't already,
+    /// the rewards are added to the caller's unclaimed rewards.
+    /// Stake received this way is not tokenized again.
+    /// To get the stake out of the contract directly, send the tokens to `unStake` instead.
+    #[payable("*")]
+    #[endpoint(unwrapActiveStake)]
+    fn unwrap_active_stake(&self) {
+        require!(self.not_paused(), "contract paused");
+        require!(
+            !self.is_global_op_in_progress(),
+            "liquid staking is temporarily paused as checkpoint is reset"
+        );
+
+        let (token_id, token_amount) = self.call_value().egld_or_single_fungible_esdt();
+        self.require_liquid_token_payment(&token_id);
+
+        let caller = self.blockchain().get_caller();
+        require!(self.can_stake(&caller), "caller not allowed to stake");
+        let user_id = NonZeroUsize::new(self.get_or_create_user(&caller))
+            .unwrap_or_else(|| sc_panic!("bad user_id"));
+
+        let _ = self.redeem_liquid_tokens(user_id, &token_amount);
+
+        self.validate_user_minimum_stake(user_id.get());
+        self.validate_user_maximum_stake(user_id.get());
+    }
+}
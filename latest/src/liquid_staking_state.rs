@@ -0,0 +1,220 @@
+use crate::events::{UnwrapActiveStakeData, WrapActiveStakeData};
+use crate::settings::OWNER_USER_ID;
+use core::num::NonZeroUsize;
+use user_fund_storage::types::FundType;
+
+multiversx_sc::imports!();
+
+/// Tokenization of active stake.
+///
+/// While the liquid staking token is set up, stake that becomes active is recorded as pending tokenization.
+/// When the delegator claims, it is moved to a pool user, registered under the contract's own address,
+/// and the delegator receives liquid staking tokens in exchange.
+/// Swaps only record the amounts, so that activating many delegators does not cost more than the swap itself.
+/// The pooled stake keeps earning rewards like any other active stake.
+/// Each token is a claim on an equal share of the pool, i.e. of its active stake and of its unclaimed rewards,
+/// so tokens are minted and burned at the current pool rate.
+///
+/// Only stake of regular accounts that opted in is tokenized. The owner, other contracts and the pool itself keep their stake as is.
+/// Nothing is tokenized while a maximum user stake is set, since tokenized stake no longer counts towards it.
+#[multiversx_sc::derive::module]
+pub trait LiquidStakingStateModule:
+    crate::settings::SettingsModule
+    + crate::rewards_state::RewardStateModule
+    + crate::events::EventsModule
+    + user_fund_storage::user_data::UserDataModule
+    + user_fund_storage::fund_module::FundModule
+    + user_fund_storage::fund_view_module::FundViewModule
+    + user_fund_storage::fund_transf_module::FundTransformationsModule
+{
+    // STORAGE
+
+    #[view(getLiquidStakingTokenId)]
+    #[storage_mapper("liquid_token_id")]
+    fn liquid_token_id(&self) -> SingleValueMapper<TokenIdentifier>;
+
+    /// Total amount of liquid staking tokens minted and not yet burned.
+    #[view(getLiquidStakingTokenSupply)]
+    #[storage_mapper("liquid_token_supply")]
+    fn liquid_token_supply(&self) -> SingleValueMapper<BigUint>;
+
+    /// Delegators opt in to have their stake tokenized when it becomes active.
+    #[storage_mapper("u_liquid_staking")]
+    fn user_liquid_staking(&self, user_id: usize) -> SingleValueMapper<bool>;
+
+    /// Stake activated since the user opted in, not yet tokenized.
+    #[storage_mapper("u_liquid_pending")]
+    fn user_liquid_pending(&self, user_id: usize) -> SingleValueMapper<BigUint>;
+
+    /// Active stake held on behalf of the token holders.
+    #[view(getLiquidStakingPoolActiveStake)]
+    fn get_liquid_pool_active_stake(&self) -> BigUint {
+        let pool_user_id = self.get_liquid_pool_user_id();
+        if pool_user_id == 0 {
+            return BigUint::zero();
+        }
+        self.get_user_stake_of_type(pool_user_id, FundType::Active)
+    }
+
+    /// Stake is only tokenized once the token is issued and the contract can mint and burn it.
+    #[view(isLiquidStakingActive)]
+    fn is_liquid_staking_active(&self) -> bool {
+        if self.liquid_token_id().is_empty() {
+            return false;
+        }
+        let roles = self
+            .blockchain()
+            .get_esdt_local_roles(&self.liquid_token_id().get());
+        roles.has_role(&EsdtLocalRole::Mint) && roles.has_role(&EsdtLocalRole::Burn)
+    }
+
+    // TOKENIZATION
+
+    /// Whether the active stake of a user gets tokenized.
+    /// Tokens cannot be sent to users without an address entry and might be refused by contracts.
+    /// The maximum user stake only looks at the stake the user holds directly,
+    /// so tokenizing while it is set would allow going around it.
+    fn is_liquid_staking_eligible(&self, user_id: usize) -> bool {
+        if user_id == OWNER_USER_ID.get()
+            || !self.user_liquid_staking(user_id).get()
+            || self.get_maximum_user_stake() > 0
+            || self.is_empty_user_address(user_id)
+        {
+            return false;
+        }
+        let user_address = self.get_user_address(user_id);
+        !self.blockchain().is_smart_contract(&user_address)
+    }
+
+    /// Records the stake activated for a user by a swap, given the user's active stake right before it.
+    /// It gets tokenized when the user claims.
+    fn add_pending_liquid_stake(&self, user_id: usize, active_before: &BigUint) {
+        let active = self.get_user_stake_of_type(user_id, FundType::Active);
+        if active > *active_before {
+            self.user_liquid_pending(user_id)
+                .update(|pending| *pending += active - active_before);
+        }
+    }
+
+    /// Moves active stake from a user to the liquid staking pool
+    /// and sends the corresponding amount of tokens to the user.
+    /// Amounts too small to yield any token stay with the user.
+    fn tokenize_active_stake(&self, user_id: usize, amount: &BigUint) {
+        let user_id_nz = NonZeroUsize::new(user_id).unwrap_or_else(|| sc_panic!("bad user_id"));
+        let pool_user_id = self.get_or_create_liquid_pool_user();
+
+        // rewards are settled before the active stake changes hands
+        self.settle_one_user_reward(user_id_nz);
+        self.settle_one_user_reward(pool_user_id);
+
+        // tokens are minted at the current pool rate, so existing holders are not diluted
+        let supply = self.liquid_token_supply().get();
+        let pool_value = self.get_liquid_pool_value(pool_user_id);
+        let token_amount = if supply == 0 || pool_value == 0 {
+            amount.clone()
+        } else {
+            amount * &supply / &pool_value
+        };
+        if token_amount == 0 {
+            return;
+        }
+
+        let user_address = self.get_user_address(user_id);
+        self.wrap_active_stake_event(
+            &user_address,
+            &WrapActiveStakeData {
+                stake_amount: amount.clone(),
+                token_amount: token_amount.clone(),
+            },
+        );
+
+        let mut remaining = amount.clone();
+        self.transfer_user_active(user_id, pool_user_id.get(), &mut remaining);
+        require!(remaining == 0, "error tokenizing active stake");
+
+        let token_id = self.liquid_token_id().get();
+        self.send().esdt_local_mint(&token_id, 0, &token_amount);
+        self.liquid_token_supply()
+            .update(|supply| *supply += &token_amount);
+        self.send()
+            .direct_esdt(&user_address, &token_id, 0, &token_amount);
+    }
+
+    /// Burns liquid staking tokens held by the contract and gives the user their share of the pool:
+    /// the active stake is transferred to the user, the rewards are added to the user's unclaimed rewards.
+    /// Returns the active stake received.
+    fn redeem_liquid_tokens(&self, user_id: NonZeroUsize, token_amount: &BigUint) -> BigUint {
+        require!(*token_amount > 0, "cannot redeem zero tokens");
+        let supply = self.liquid_token_supply().get();
+        require!(
+            *token_amount <= supply,
+            "cannot redeem more than the liquid staking token supply"
+        );
+
+        let pool_user_id = self.get_or_create_liquid_pool_user();
+
+        // rewards are settled before the active stake changes hands
+        self.settle_one_user_reward(user_id);
+        self.settle_one_user_reward(pool_user_id);
+
+        let pool_active = self.get_user_stake_of_type(pool_user_id.get(), FundType::Active);
+        let mut pool_rewards = self.get_user_rew_unclaimed(pool_user_id);
+        let stake_amount = &pool_active * token_amount / &supply;
+        let reward_amount = &pool_rewards * token_amount / &supply;
+        require!(stake_amount > 0, "redeemed amount too small");
+
+        let user_address = self.get_user_address(user_id.get());
+        self.unwrap_active_stake_event(
+            &user_address,
+            &UnwrapActiveStakeData {
+                token_amount: token_amount.clone(),
+                stake_amount: stake_amount.clone(),
+                reward_amount: reward_amount.clone(),
+            },
+        );
+
+        let token_id = self.liquid_token_id().get();
+        self.send().esdt_local_burn(&token_id, 0, token_amount);
+        self.liquid_token_supply()
+            .update(|supply| *supply -= token_amount);
+
+        let mut remaining = stake_amount.clone();
+        self.transfer_user_active(pool_user_id.get(), user_id.get(), &mut remaining);
+        require!(remaining == 0, "error redeeming liquid staking tokens");
+
+        if reward_amount > 0 {
+            pool_rewards -= &reward_amount;
+            self.set_user_rew_unclaimed(pool_user_id, &pool_rewards);
+            let user_rewards = self.get_user_rew_unclaimed(user_id) + &reward_amount;
+            self.set_user_rew_unclaimed(user_id, &user_rewards);
+        }
+
+        stake_amount
+    }
+
+    // UTILS
+
+    fn require_liquid_token_payment(&self, token_id: &EgldOrEsdtTokenIdentifier) {
+        require!(self.is_liquid_staking_active(), "liquid staking not active");
+        require!(
+            *token_id == EgldOrEsdtTokenIdentifier::esdt(self.liquid_token_id().get()),
+            "only the liquid staking token is accepted"
+        );
+    }
+
+    /// 0 if no stake was ever tokenized.
+    fn get_liquid_pool_user_id(&self) -> usize {
+        self.get_user_id(&self.blockchain().get_sc_address())
+    }
+
+    fn get_or_create_liquid_pool_user(&self) -> NonZeroUsize {
+        NonZeroUsize::new(self.get_or_create_user(&self.blockchain().get_sc_address()))
+            .unwrap_or_else(|| sc_panic!("bad user_id"))
+    }
+
+    /// Everything the token holders are entitled to: the pool active stake and its unclaimed rewards.
+    fn get_liquid_pool_value(&self, pool_user_id: NonZeroUsize) -> BigUint {
+        self.get_user_stake_of_type(pool_user_id.get(), FundType::Active)
+            + self.get_user_rew_unclaimed(pool_user_id)
+    }
+}
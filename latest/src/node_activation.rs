@@ -1,8 +1,12 @@
 use crate::auction_proxy;
+use crate::events::TopUpChangeData;
+use crate::roles::Role;
 use node_storage::{
-    node_config::NodeIndexArrayVec,
+    node_config::{NodeErrorArrayVec, NodeIndexArrayVec},
     types::{BLSKey, BLSSignature, BLSStatusMultiArg, NodeState},
 };
+use user_fund_storage::fund_view_module::USER_STAKE_TOTALS_ID;
+use user_fund_storage::types::FundType;
 
 multiversx_sc::imports!();
 
@@ -16,20 +20,25 @@ pub trait NodeActivationModule:
     + crate::settings::SettingsModule
     + crate::reset_checkpoint_state::ResetCheckpointStateModule
     + crate::rewards_state::RewardStateModule
+    + crate::liquid_staking_state::LiquidStakingStateModule
     + crate::user_stake_state::UserStakeStateModule
     + crate::events::EventsModule
+    + crate::keeper_bounty::KeeperBountyModule
+    + crate::roles::RolesModule
+    + multiversx_sc_modules::pause::PauseModule
 {
     #[proxy]
     fn auction_proxy(&self, to: ManagedAddress) -> auction_proxy::Proxy<Self::Api>;
 
-    /// Owner activates specific nodes.
-    #[only_owner]
+    /// Owner or node operators activate specific nodes.
     #[endpoint(stakeNodes)]
     fn stake_nodes(
         &self,
         amount_to_stake: BigUint,
         bls_keys: MultiValueManagedVec<Self::Api, BLSKey<Self::Api>>,
     ) {
+        self.require_role(Role::NodeOperator);
+
         require!(
             !self.is_bootstrap_mode(),
             "cannot stake nodes in bootstrap mode"
@@ -66,7 +75,7 @@ pub trait NodeActivationModule:
             let bls_signature = self.get_node_signature(node_id);
             bls_keys_signatures.push((bls_key, bls_signature).into());
 
-            self.set_node_state(node_id, NodeState::PendingActivation);
+            self.change_node_state(node_id, NodeState::PendingActivation, 0);
         }
 
         self.perform_stake_nodes(node_ids, bls_keys_signatures, amount_to_stake);
@@ -84,11 +93,18 @@ pub trait NodeActivationModule:
         // send all stake to auction contract
         let auction_contract_addr = self.get_auction_contract_address();
 
+        // the stake is returned in the callback if the call fails
+        self.auction_staked_tokens()
+            .update(|staked| *staked += &amount_to_stake);
+
         self.auction_proxy(auction_contract_addr)
             .stake(num_nodes, bls_keys_signatures)
-            .with_egld_transfer(amount_to_stake)
+            .with_egld_transfer(amount_to_stake.clone())
             .async_call()
-            .with_callback(self.callbacks().auction_stake_callback(node_ids))
+            .with_callback(
+                self.callbacks()
+                    .auction_stake_callback(node_ids, amount_to_stake),
+            )
             .call_and_exit()
     }
 
@@ -98,52 +114,80 @@ pub trait NodeActivationModule:
     fn auction_stake_callback(
         &self,
         node_ids: NodeIndexArrayVec,
+        amount_to_stake: BigUint,
         #[call_result] call_result: ManagedAsyncCallResult<
             MultiValueEncoded<BLSStatusMultiArg<Self::Api>>,
         >,
     ) {
         match call_result {
             ManagedAsyncCallResult::Ok(node_status_args) => {
-                let (node_ids_ok, node_ids_fail) =
+                let num_nodes = node_ids.len();
+                let (node_ids_ok, failed_nodes) =
                     self.split_node_ids_by_err(node_ids, node_status_args);
-                self.auction_stake_callback_ok(&node_ids_ok);
+                let assigned_stake =
+                    self.auction_stake_callback_ok(&node_ids_ok, &amount_to_stake, num_nodes);
+                // the stake of the failed nodes is not held by the auction contract
+                self.release_auction_stake(&(&amount_to_stake - &assigned_stake));
                 self.auction_stake_callback_fail(
-                    &node_ids_fail,
+                    &failed_nodes,
                     &ManagedBuffer::from(b"staking failed for some nodes"),
                 );
             }
             ManagedAsyncCallResult::Err(error) => {
-                self.auction_stake_callback_fail(&node_ids, &error.err_msg)
+                let returned_stake = self.call_value().egld_value();
+                self.release_auction_stake(&returned_stake);
+                let failed_nodes = self.all_nodes_failed(&node_ids, error.err_code);
+                self.auction_stake_callback_fail(&failed_nodes, &error.err_msg)
             }
         }
     }
 
-    fn auction_stake_callback_ok(&self, node_ids: &NodeIndexArrayVec) {
+    /// The stake is split evenly between all the nodes sent to the auction contract.
+    /// The first node staked successfully also gets the rounding remainder, so that all of it can be released later.
+    /// Returns the stake assigned to the nodes.
+    fn auction_stake_callback_ok(
+        &self,
+        node_ids: &NodeIndexArrayVec,
+        amount_to_stake: &BigUint,
+        num_nodes: usize,
+    ) -> BigUint {
+        let mut assigned_stake = BigUint::zero();
         if node_ids.is_empty() {
-            return;
+            return assigned_stake;
         }
 
+        let stake_per_node = amount_to_stake / num_nodes as u32;
+        let mut remainder = amount_to_stake - &(&stake_per_node * num_nodes as u32);
+
         // set nodes to Active
         for &node_id in node_ids.iter() {
-            self.set_node_state(node_id, NodeState::Active);
+            self.change_node_state(node_id, NodeState::Active, 0);
+            let node_stake = &stake_per_node + &core::mem::replace(&mut remainder, BigUint::zero());
+            self.node_auction_stake(node_id).set(&node_stake);
+            assigned_stake += node_stake;
         }
 
-        // log event (no data)
-        // TODO: log BLS keys of nodes in data
+        // individual nodes are logged by the state change events
         self.stake_node_ok_event();
+
+        assigned_stake
     }
 
-    fn auction_stake_callback_fail(&self, node_ids: &NodeIndexArrayVec, err_msg: &ManagedBuffer) {
-        if node_ids.is_empty() {
+    fn auction_stake_callback_fail(
+        &self,
+        failed_nodes: &NodeErrorArrayVec,
+        err_msg: &ManagedBuffer,
+    ) {
+        if failed_nodes.is_empty() {
             return;
         }
 
         // set nodes to Inactive
-        for &node_id in node_ids.iter() {
-            self.set_node_state(node_id, NodeState::Inactive);
+        for &(node_id, error_code) in failed_nodes.iter() {
+            self.change_node_state(node_id, NodeState::Inactive, error_code);
         }
 
-        // log failure event (no data)
+        // individual nodes are logged by the state change events
         self.stake_node_fail_event(err_msg);
     }
 
@@ -151,24 +195,26 @@ pub trait NodeActivationModule:
 
     /// Unstakes from the auction smart contract.
     /// The nodes will stop receiving rewards, but stake cannot be yet reclaimed.
-    /// This operation is performed by the owner.
+    /// This operation is performed by the owner or by node operators.
     /// Does not unstake tokens.
-    #[only_owner]
     #[endpoint(unStakeNodes)]
     fn unstake_nodes_endpoint(&self, bls_keys: MultiValueManagedVec<Self::Api, BLSKey<Self::Api>>) {
+        self.require_role(Role::NodeOperator);
+
         self.unstake_nodes(false, bls_keys)
     }
 
     /// Unstakes from the auction smart contract.
     /// The nodes will stop receiving rewards, but stake cannot be yet reclaimed.
-    /// This operation is performed by the owner.
+    /// This operation is performed by the owner or by node operators.
     /// Also unstakes tokens.
-    #[only_owner]
     #[endpoint(unStakeNodesAndTokens)]
     fn unstake_nodes_and_tokens_endpoint(
         &self,
         bls_keys: MultiValueManagedVec<Self::Api, BLSKey<Self::Api>>,
     ) {
+        self.require_role(Role::NodeOperator);
+
         self.unstake_nodes(true, bls_keys)
     }
 
@@ -205,7 +251,7 @@ pub trait NodeActivationModule:
                 "node not active"
             );
 
-            self.set_node_state(node_id, NodeState::PendingDeactivation);
+            self.change_node_state(node_id, NodeState::PendingDeactivation, 0);
         }
 
         // send unstake command to Auction SC
@@ -214,13 +260,19 @@ pub trait NodeActivationModule:
             self.auction_proxy(auction_contract_addr)
                 .unstake(bls_keys)
                 .async_call()
-                .with_callback(self.callbacks().auction_unstake_callback(node_ids))
+                .with_callback(
+                    self.callbacks()
+                        .auction_unstake_callback(node_ids, unstake_tokens),
+                )
                 .call_and_exit()
         } else {
             self.auction_proxy(auction_contract_addr)
                 .unstake_nodes(bls_keys)
                 .async_call()
-                .with_callback(self.callbacks().auction_unstake_callback(node_ids))
+                .with_callback(
+                    self.callbacks()
+                        .auction_unstake_callback(node_ids, unstake_tokens),
+                )
                 .call_and_exit()
         }
     }
@@ -231,27 +283,29 @@ pub trait NodeActivationModule:
     fn auction_unstake_callback(
         &self,
         node_ids: NodeIndexArrayVec,
+        unstake_tokens: bool,
         #[call_result] call_result: ManagedAsyncCallResult<
             MultiValueEncoded<BLSStatusMultiArg<Self::Api>>,
         >,
     ) {
         match call_result {
             ManagedAsyncCallResult::Ok(node_status_args) => {
-                let (node_ids_ok, node_ids_fail) =
+                let (node_ids_ok, failed_nodes) =
                     self.split_node_ids_by_err(node_ids, node_status_args);
-                self.auction_unstake_callback_ok(&node_ids_ok);
+                self.auction_unstake_callback_ok(&node_ids_ok, unstake_tokens);
                 self.auction_unstake_callback_fail(
-                    &node_ids_fail,
+                    &failed_nodes,
                     &ManagedBuffer::from(b"unstaking failed for some nodes"),
                 );
             }
             ManagedAsyncCallResult::Err(error) => {
-                self.auction_unstake_callback_fail(&node_ids, &error.err_msg)
+                let failed_nodes = self.all_nodes_failed(&node_ids, error.err_code);
+                self.auction_unstake_callback_fail(&failed_nodes, &error.err_msg)
             }
         }
     }
 
-    fn auction_unstake_callback_ok(&self, node_ids: &NodeIndexArrayVec) {
+    fn auction_unstake_callback_ok(&self, node_ids: &NodeIndexArrayVec, unstake_tokens: bool) {
         if node_ids.is_empty() {
             return;
         }
@@ -259,11 +313,14 @@ pub trait NodeActivationModule:
         // set nodes to UnBondPeriod + save current block nonce
         let bl_nonce = self.blockchain().get_block_nonce();
         for &node_id in node_ids.iter() {
-            self.set_node_state(node_id, NodeState::UnBondPeriod { started: bl_nonce });
+            self.change_node_state(node_id, NodeState::UnBondPeriod { started: bl_nonce }, 0);
+            if unstake_tokens {
+                // the node stake is no longer staked, it comes back when the node is unbonded
+                self.release_auction_stake(&self.node_auction_stake(node_id).take());
+            }
         }
 
-        // log event (no data)
-        // TODO: log BLS keys of nodes in data
+        // individual nodes are logged by the state change events
         self.unstake_node_ok_event();
     }
 
@@ -278,32 +335,37 @@ pub trait NodeActivationModule:
         for bls_key in bls_keys.iter() {
             let node_id = self.get_node_id(&bls_key);
             require!(node_id != 0, "unknown node provided");
-            self.set_node_state(node_id, NodeState::UnBondPeriod { started: 0 });
+            self.change_node_state(node_id, NodeState::UnBondPeriod { started: 0 }, 0);
         }
     }
 
-    fn auction_unstake_callback_fail(&self, node_ids: &NodeIndexArrayVec, err_msg: &ManagedBuffer) {
-        if node_ids.is_empty() {
+    fn auction_unstake_callback_fail(
+        &self,
+        failed_nodes: &NodeErrorArrayVec,
+        err_msg: &ManagedBuffer,
+    ) {
+        if failed_nodes.is_empty() {
             return;
         }
 
         // revert nodes to Active
-        for &node_id in node_ids.iter() {
-            self.set_node_state(node_id, NodeState::Active);
+        for &(node_id, error_code) in failed_nodes.iter() {
+            self.change_node_state(node_id, NodeState::Active, error_code);
         }
 
-        // log failure event (no data)
+        // individual nodes are logged by the state change events
         self.unstake_node_fail_event(err_msg);
     }
 
     // UNBOND
     /// Calls unbond for all provided nodes. Will fail if node cannot be unbonded.
-    #[only_owner]
     #[endpoint(unBondNodes)]
     fn unbond_specific_nodes_endpoint(
         &self,
         bls_keys: MultiValueManagedVec<Self::Api, BLSKey<Self::Api>>,
     ) {
+        self.require_role(Role::NodeOperator);
+
         require!(
             !self.is_global_op_in_progress(),
             "node operations are temporarily paused as checkpoint is reset"
@@ -322,14 +384,16 @@ pub trait NodeActivationModule:
             node_ids.push(node_id);
         }
 
-        self.perform_unbond(node_ids, bls_keys);
+        self.perform_unbond(node_ids, bls_keys, None);
     }
 
     /// Calls unbond for all nodes that are in the unbond period and are due.
     /// Nothing happens if no nodes can be unbonded.
-    #[only_owner]
+    /// The keeper bounty is paid in the callback, if at least one node was unbonded.
     #[endpoint(unBondAllPossibleNodes)]
     fn unbond_all_possible_nodes(&self) {
+        self.require_any_role(&[Role::NodeOperator, Role::Keeper]);
+
         require!(
             !self.is_global_op_in_progress(),
             "node operations are temporarily paused as checkpoint is reset"
@@ -348,17 +412,19 @@ pub trait NodeActivationModule:
         }
 
         if !node_ids.is_empty() {
-            self.perform_unbond(node_ids, bls_keys);
+            let keeper = self.blockchain().get_caller();
+            self.perform_unbond(node_ids, bls_keys, Some(keeper));
         }
     }
 
     fn prepare_node_for_unbond_if_possible(&self, node_id: usize) -> bool {
         if let NodeState::UnBondPeriod { started } = self.get_node_state(node_id) {
-            self.set_node_state(
+            self.change_node_state(
                 node_id,
                 NodeState::PendingUnBond {
                     unbond_started: started,
                 },
+                0,
             );
             return true;
         }
@@ -370,13 +436,17 @@ pub trait NodeActivationModule:
         &self,
         node_ids: NodeIndexArrayVec,
         bls_keys: MultiValueManagedVec<BLSKey<Self::Api>>,
+        opt_keeper: Option<ManagedAddress>,
     ) {
         // send unbond command to Auction SC
         let auction_contract_addr = self.get_auction_contract_address();
         self.auction_proxy(auction_contract_addr)
             .unbond_nodes(bls_keys)
             .async_call()
-            .with_callback(self.callbacks().auction_unbond_callback(node_ids))
+            .with_callback(
+                self.callbacks()
+                    .auction_unbond_callback(node_ids, opt_keeper),
+            )
             .call_and_exit()
     }
 
@@ -386,22 +456,29 @@ pub trait NodeActivationModule:
     fn auction_unbond_callback(
         &self,
         node_ids: NodeIndexArrayVec,
+        opt_keeper: Option<ManagedAddress>,
         #[call_result] call_result: ManagedAsyncCallResult<
             MultiValueEncoded<BLSStatusMultiArg<Self::Api>>,
         >,
     ) {
         match call_result {
             ManagedAsyncCallResult::Ok(node_status_args) => {
-                let (node_ids_ok, node_ids_fail) =
+                let (node_ids_ok, failed_nodes) =
                     self.split_node_ids_by_err(node_ids, node_status_args);
+                if let Some(keeper) = opt_keeper {
+                    if !node_ids_ok.is_empty() {
+                        self.pay_keeper_bounty_to(&keeper, b"unBondAllPossibleNodes", b"");
+                    }
+                }
                 self.auction_unbond_callback_ok(&node_ids_ok);
                 self.auction_unbond_callback_fail(
-                    &node_ids_fail,
+                    &failed_nodes,
                     &ManagedBuffer::from(b"unbonding failed for some nodes"),
                 );
             }
             ManagedAsyncCallResult::Err(error) => {
-                self.auction_unbond_callback_fail(&node_ids, &error.err_msg)
+                let failed_nodes = self.all_nodes_failed(&node_ids, error.err_code);
+                self.auction_unbond_callback_fail(&failed_nodes, &error.err_msg)
             }
         }
     }
@@ -412,42 +489,57 @@ pub trait NodeActivationModule:
         }
 
         // set nodes to Inactive + reset unstake nonce since it is no longer needed
+        // the node stake leaves the auction contract, unless it was already released on unstake
         for &node_id in node_ids.iter() {
-            self.set_node_state(node_id, NodeState::Inactive);
+            self.change_node_state(node_id, NodeState::Inactive, 0);
+            self.release_auction_stake(&self.node_auction_stake(node_id).take());
         }
 
-        // log event (no data)
-        // TODO: log BLS keys of nodes in data
+        // individual nodes are logged by the state change events
         self.unbond_node_ok_event();
     }
 
-    fn auction_unbond_callback_fail(&self, node_ids: &NodeIndexArrayVec, err_msg: &ManagedBuffer) {
-        if node_ids.is_empty() {
+    fn auction_unbond_callback_fail(
+        &self,
+        failed_nodes: &NodeErrorArrayVec,
+        err_msg: &ManagedBuffer,
+    ) {
+        if failed_nodes.is_empty() {
             return;
         }
 
         // revert nodes to UnBondPeriod
-        for &node_id in node_ids.iter() {
+        for &(node_id, error_code) in failed_nodes.iter() {
             if let NodeState::PendingUnBond { unbond_started } = self.get_node_state(node_id) {
-                self.set_node_state(
+                self.change_node_state(
                     node_id,
                     NodeState::UnBondPeriod {
                         started: unbond_started,
                     },
+                    error_code,
                 );
             } else {
                 sc_panic!("node not pending unbond");
             }
         }
 
-        // log failure event (no data)
+        // individual nodes are logged by the state change events
         self.unbond_node_fail_event(err_msg);
     }
 
+    /// Used when the entire auction call failed, so all nodes get the same error code.
+    fn all_nodes_failed(&self, node_ids: &NodeIndexArrayVec, error_code: u32) -> NodeErrorArrayVec {
+        node_ids
+            .iter()
+            .map(|&node_id| (node_id, error_code))
+            .collect()
+    }
+
     /// Claims from auction SC funds that were sent but are not required to run the nodes.
-    #[only_owner]
     #[endpoint(claimUnusedFunds)]
     fn claim_unused_funds(&self) {
+        self.require_role(Role::NodeOperator);
+
         require!(
             !self.is_global_op_in_progress(),
             "node operations are temporarily paused as checkpoint is reset"
@@ -461,7 +553,6 @@ pub trait NodeActivationModule:
             .call_and_exit()
     }
 
-    #[only_owner]
     #[payable("EGLD")]
     #[endpoint(unJailNodes)]
     fn unjail_nodes(
@@ -469,6 +560,8 @@ pub trait NodeActivationModule:
         bls_keys: MultiValueManagedVec<Self::Api, BLSKey<Self::Api>>,
         #[payment] fine_payment: BigUint,
     ) {
+        self.require_role(Role::NodeOperator);
+
         // validation only
         for bls_key in bls_keys.iter() {
             let node_id = self.get_node_id(&bls_key);
@@ -488,23 +581,297 @@ pub trait NodeActivationModule:
             .call_and_exit()
     }
 
+    // TOKENS
+
+    /// Tokens held by the auction contract as stake, as far as this contract knows.
+    /// Increased by `stakeNodes` and `stakeTopUp`.
+    /// Decreased by `unStakeTokens` and `unStakeTopUp`, for nodes that fail to stake,
+    /// and by the node stake on `unStakeNodesAndTokens` or, failing that, when the node is unbonded.
+    #[view(getAuctionStakedTokens)]
+    #[storage_mapper("auction_staked_tokens")]
+    fn auction_staked_tokens(&self) -> SingleValueMapper<BigUint>;
+
+    /// Stake sent to the auction contract for a node, until it leaves the auction contract.
+    /// Empty for nodes staked before the stake was tracked.
+    #[view(getNodeAuctionStake)]
+    #[storage_mapper("node_auction_stake")]
+    fn node_auction_stake(&self, node_id: usize) -> SingleValueMapper<BigUint>;
+
+    /// Tokens unstaked from the auction contract that were not yet unbonded.
+    #[view(getAuctionUnstakedTokens)]
+    #[storage_mapper("auction_unstaked_tokens")]
+    fn auction_unstaked_tokens(&self) -> SingleValueMapper<BigUint>;
+
+    /// Part of the auction staked tokens above the stake required by the nodes.
+    /// Increased by `stakeTopUp`, decreased by `unStakeTopUp` and `unStakeTokens`.
+    #[view(getAuctionTopUp)]
+    #[storage_mapper("auction_top_up")]
+    fn auction_top_up(&self) -> SingleValueMapper<BigUint>;
+
+    /// Owner can correct the tracked auction stake, e.g. for contracts that staked nodes
+    /// before the stake was tracked.
+    /// Warning: misuse can allow unstaking tokens needed for active stake.
+    #[only_owner]
+    #[endpoint(forceAuctionStakedTokens)]
+    fn force_auction_staked_tokens(&self, amount: BigUint) {
+        self.auction_staked_tokens().set(&amount);
+        self.auction_top_up().update(|top_up| {
+            if *top_up > amount {
+                *top_up = amount;
+            }
+        });
+    }
+
+    /// Node stake leaving the auction contract. The top-up cannot exceed what is left.
+    /// Saturates, since the owner might have forced the tracked stake lower in the meantime.
+    fn release_auction_stake(&self, amount: &BigUint) {
+        if *amount == 0 {
+            return;
+        }
+        let staked = self.auction_staked_tokens().update(|staked| {
+            if *staked > *amount {
+                *staked -= amount;
+            } else {
+                *staked = BigUint::zero();
+            }
+            staked.clone()
+        });
+        self.auction_top_up().update(|top_up| {
+            if *top_up > staked {
+                *top_up = staked;
+            }
+        });
+    }
+
+    /// Sends stake to the auction contract beyond what the nodes require.
+    /// Same as for `stakeNodes`, the stake comes out of the unprotected funds.
+    #[endpoint(stakeTopUp)]
+    fn stake_top_up(&self, amount: BigUint) {
+        self.require_role(Role::NodeOperator);
+
+        require!(
+            !self.is_bootstrap_mode(),
+            "cannot stake top-up in bootstrap mode"
+        );
+
+        require!(
+            !self.is_global_op_in_progress(),
+            "node operations are temporarily paused as checkpoint is reset"
+        );
+
+        require!(amount > 0, "cannot stake zero tokens");
+
+        require!(
+            self.total_unprotected() >= amount,
+            "not enough funds in contract to stake top-up"
+        );
+
+        // reverted in the callback if the call fails
+        self.auction_staked_tokens()
+            .update(|staked| *staked += &amount);
+        self.auction_top_up().update(|top_up| *top_up += &amount);
+
+        self.stake_top_up_event(&amount);
+        let auction_contract_addr = self.get_auction_contract_address();
+        self.auction_proxy(auction_contract_addr)
+            .stake(0usize, MultiValueEncoded::new())
+            .with_egld_transfer(amount.clone())
+            .async_call()
+            .with_callback(self.callbacks().auction_stake_top_up_callback(&amount))
+            .call_and_exit()
+    }
+
+    #[callback]
+    fn auction_stake_top_up_callback(
+        &self,
+        amount: &BigUint,
+        #[call_result] call_result: ManagedAsyncCallResult<MultiValueEncoded<ManagedBuffer>>,
+    ) {
+        match call_result {
+            ManagedAsyncCallResult::Ok(_) => {
+                self.stake_top_up_ok_event(&TopUpChangeData {
+                    amount: amount.clone(),
+                    total_top_up: self.auction_top_up().get(),
+                });
+            }
+            ManagedAsyncCallResult::Err(error) => {
+                // also caps the top-up to what is left of the staked tokens
+                self.release_auction_stake(amount);
+                self.auction_top_up().update(|top_up| {
+                    if *top_up > *amount {
+                        *top_up -= amount;
+                    } else {
+                        *top_up = BigUint::zero();
+                    }
+                });
+                self.stake_top_up_fail_event(&error.err_msg);
+            }
+        }
+    }
+
+    /// Unstakes part of the top-up from the auction contract.
+    /// The tokens can then be unbonded with `unBondTokens`, same as those unstaked with `unStakeTokens`.
+    #[endpoint(unStakeTopUp)]
+    fn unstake_top_up(&self, amount: BigUint) {
+        self.require_role(Role::NodeOperator);
+
+        require!(
+            !self.is_global_op_in_progress(),
+            "node operations are temporarily paused as checkpoint is reset"
+        );
+
+        require!(amount > 0, "cannot unstake zero tokens");
+
+        let top_up = self.auction_top_up().get();
+        require!(amount <= top_up, "cannot unstake more than the top-up");
+
+        // reserved until the callback, so concurrent calls cannot unstake the same tokens
+        self.auction_top_up().set(&(top_up - &amount));
+        self.auction_staked_tokens()
+            .update(|staked| *staked -= &amount);
+
+        self.unstake_top_up_event(&amount);
+        let auction_contract_addr = self.get_auction_contract_address();
+        self.auction_proxy(auction_contract_addr)
+            .unstake_tokens(&amount)
+            .async_call()
+            .with_callback(self.callbacks().auction_unstake_top_up_callback(&amount))
+            .call_and_exit()
+    }
+
+    #[callback]
+    fn auction_unstake_top_up_callback(
+        &self,
+        amount: &BigUint,
+        #[call_result] call_result: ManagedAsyncCallResult<()>,
+    ) {
+        match call_result {
+            ManagedAsyncCallResult::Ok(()) => {
+                self.auction_unstaked_tokens()
+                    .update(|unstaked| *unstaked += amount);
+                self.unstake_top_up_ok_event(&TopUpChangeData {
+                    amount: amount.clone(),
+                    total_top_up: self.auction_top_up().get(),
+                });
+            }
+            ManagedAsyncCallResult::Err(error) => {
+                self.auction_staked_tokens()
+                    .update(|staked| *staked += amount);
+                self.auction_top_up().update(|top_up| *top_up += amount);
+                self.unstake_top_up_fail_event(&error.err_msg);
+            }
+        }
+    }
+
+    /// Unstakes tokens from the auction contract, without unstaking any nodes.
+    /// Only tokens not needed for the active stake of the delegators can be unstaked.
+    /// The auction contract takes them out of the top-up, so the tracked top-up decreases too.
     #[endpoint(unStakeTokens)]
     fn unstake_tokens(&self, amount: BigUint) {
+        self.require_role(Role::NodeOperator);
+
+        require!(
+            !self.is_global_op_in_progress(),
+            "node operations are temporarily paused as checkpoint is reset"
+        );
+
+        require!(amount > 0, "cannot unstake zero tokens");
+
+        let total_active = self.get_user_stake_of_type(USER_STAKE_TOTALS_ID, FundType::Active);
+        let staked_tokens = self.auction_staked_tokens().get();
+        require!(
+            &amount + &total_active <= staked_tokens,
+            "cannot unstake tokens needed for active stake"
+        );
+
+        // reserved until the callback, so concurrent calls cannot unstake the same tokens
+        self.auction_staked_tokens().set(&(staked_tokens - &amount));
+        let top_up = self.auction_top_up().get();
+        let top_up_amount = core::cmp::min(&amount, &top_up).clone();
+        self.auction_top_up().set(&(top_up - &top_up_amount));
+
         self.unstake_tokens_event(&amount);
         let auction_contract_addr = self.get_auction_contract_address();
         self.auction_proxy(auction_contract_addr)
             .unstake_tokens(&amount)
             .async_call()
+            .with_callback(
+                self.callbacks()
+                    .auction_unstake_tokens_callback(&amount, &top_up_amount),
+            )
             .call_and_exit()
     }
 
+    #[callback]
+    fn auction_unstake_tokens_callback(
+        &self,
+        amount: &BigUint,
+        top_up_amount: &BigUint,
+        #[call_result] call_result: ManagedAsyncCallResult<()>,
+    ) {
+        match call_result {
+            ManagedAsyncCallResult::Ok(()) => {
+                self.auction_unstaked_tokens()
+                    .update(|unstaked| *unstaked += amount);
+                self.unstake_tokens_ok_event(amount);
+            }
+            ManagedAsyncCallResult::Err(error) => {
+                self.auction_staked_tokens()
+                    .update(|staked| *staked += amount);
+                self.auction_top_up()
+                    .update(|top_up| *top_up += top_up_amount);
+                self.unstake_tokens_fail_event(&error.err_msg);
+            }
+        }
+    }
+
+    /// Unbonds tokens previously unstaked with `unStakeTokens` or `unStakeTopUp`,
+    /// the auction contract sends them back.
     #[endpoint(unBondTokens)]
     fn unbond_tokens(&self, amount: BigUint) {
+        self.require_role(Role::NodeOperator);
+
+        require!(
+            !self.is_global_op_in_progress(),
+            "node operations are temporarily paused as checkpoint is reset"
+        );
+
+        require!(amount > 0, "cannot unbond zero tokens");
+
+        let unstaked_tokens = self.auction_unstaked_tokens().get();
+        require!(
+            amount <= unstaked_tokens,
+            "cannot unbond more than the unstaked tokens"
+        );
+
+        // reserved until the callback, so concurrent calls cannot unbond the same tokens
+        self.auction_unstaked_tokens()
+            .set(&(unstaked_tokens - &amount));
+
         self.unbond_tokens_event(&amount);
         let auction_contract_addr = self.get_auction_contract_address();
         self.auction_proxy(auction_contract_addr)
             .unbond_tokens(&amount)
             .async_call()
+            .with_callback(self.callbacks().auction_unbond_tokens_callback(&amount))
             .call_and_exit()
     }
+
+    #[callback]
+    fn auction_unbond_tokens_callback(
+        &self,
+        amount: &BigUint,
+        #[call_result] call_result: ManagedAsyncCallResult<()>,
+    ) {
+        match call_result {
+            ManagedAsyncCallResult::Ok(()) => {
+                self.unbond_tokens_ok_event(amount);
+            }
+            ManagedAsyncCallResult::Err(error) => {
+                self.auction_unstaked_tokens()
+                    .update(|unstaked| *unstaked += amount);
+                self.unbond_tokens_fail_event(&error.err_msg);
+            }
+        }
+    }
 }
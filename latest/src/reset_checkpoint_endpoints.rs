@@ -1,20 +1,24 @@
 use crate::reset_checkpoint_types::{
-    ComputeAllRewardsData, GlobalOpCheckpoint, ModifyDelegationCapStep,
-    ModifyTotalDelegationCapData,
+    GlobalOpCheckpoint, GlobalOpProgress, ModifyDelegationCapStep, ModifyTotalDelegationCapData,
 };
-use crate::settings::{OWNER_USER_ID, PERCENTAGE_DENOMINATOR};
+use crate::roles::Role;
+use crate::settings::{ServiceFeeAnnouncement, PERCENTAGE_DENOMINATOR};
 use core::cmp::Ordering;
 use user_fund_storage::fund_view_module::USER_STAKE_TOTALS_ID;
 use user_fund_storage::types::FundType;
 
 multiversx_sc::imports!();
 
-pub const STOP_AT_GASLIMIT: u64 = 100_000_000;
-
 #[multiversx_sc::derive::module]
 pub trait ResetCheckpointsModule:
     crate::rewards_state::RewardStateModule
     + crate::reset_checkpoint_state::ResetCheckpointStateModule
+    + crate::liquid_staking_state::LiquidStakingStateModule
+    + crate::user_stake_state::UserStakeStateModule
+    + crate::events::EventsModule
+    + crate::keeper_bounty::KeeperBountyModule
+    + crate::roles::RolesModule
+    + multiversx_sc_modules::pause::PauseModule
     + multiversx_sc_modules::features::FeaturesModule
     + user_fund_storage::user_data::UserDataModule
     + user_fund_storage::fund_module::FundModule
@@ -24,21 +28,34 @@ pub trait ResetCheckpointsModule:
 {
     /// Continues executing any interrupted operation.
     /// Returns true if still out of gas, false if computation completed.
+    /// Pays the keeper bounty if the operation advanced, rate limited per step the call started from.
+    /// Optionally, the gas threshold at which to interrupt can be overridden for this call.
     #[endpoint(continueGlobalOperation)]
-    fn continue_global_operation_endpoint(&self) -> OperationCompletionStatus {
+    fn continue_global_operation_endpoint(
+        &self,
+        opt_gas_threshold: OptionalValue<u64>,
+    ) -> OperationCompletionStatus {
         self.check_feature_on(b"continueGlobalOperation", true);
 
-        let orc = self.global_op_checkpoint().get();
-        self.continue_global_operation(orc)
+        let gas_threshold =
+            self.resolve_gas_threshold(self.get_global_op_gas_threshold(), opt_gas_threshold);
+        let orc_before = self.global_op_checkpoint().get();
+        let (operation, step) = self.global_op_step_names(&orc_before);
+        let status = self.continue_global_operation(orc_before.clone(), gas_threshold);
+        if self.global_op_checkpoint().get() != orc_before {
+            self.pay_keeper_bounty(operation, step);
+        }
+        status
     }
 
     fn continue_global_operation(
         &self,
         mut orc: GlobalOpCheckpoint<Self::Api>,
+        gas_threshold: u64,
     ) -> OperationCompletionStatus {
         let mut status = OperationCompletionStatus::Completed;
         while matches!(status, OperationCompletionStatus::Completed) && !orc.is_none() {
-            let (new_status, new_orc) = self.continue_global_operation_step(orc);
+            let (new_status, new_orc) = self.continue_global_operation_step(orc, gas_threshold);
             status = new_status;
             orc = new_orc;
         }
@@ -50,32 +67,23 @@ pub trait ResetCheckpointsModule:
     fn continue_global_operation_step(
         &self,
         orc: GlobalOpCheckpoint<Self::Api>,
+        gas_threshold: u64,
     ) -> (OperationCompletionStatus, GlobalOpCheckpoint<Self::Api>) {
         match orc {
             GlobalOpCheckpoint::None => (OperationCompletionStatus::Completed, orc),
             GlobalOpCheckpoint::ModifyTotalDelegationCap(mdcap_data) => {
-                self.continue_modify_total_delegation_cap_step(mdcap_data)
+                self.continue_modify_total_delegation_cap_step(mdcap_data, gas_threshold)
             }
             GlobalOpCheckpoint::ChangeServiceFee {
-                new_service_fee,
-                compute_rewards_data,
+                new_service_fee, ..
             } => {
-                if let Some(more_computation) = self.compute_all_rewards(compute_rewards_data) {
-                    (
-                        OperationCompletionStatus::InterruptedBeforeOutOfGas,
-                        GlobalOpCheckpoint::ChangeServiceFee {
-                            new_service_fee,
-                            compute_rewards_data: more_computation,
-                        },
-                    )
-                } else {
-                    // finish
-                    self.set_service_fee(new_service_fee);
-                    (
-                        OperationCompletionStatus::Completed,
-                        GlobalOpCheckpoint::None,
-                    )
-                }
+                // only left over from before the reward index, the fee can be changed right away
+                self.update_reward_index();
+                self.set_service_fee(new_service_fee);
+                (
+                    OperationCompletionStatus::Completed,
+                    GlobalOpCheckpoint::None,
+                )
             }
         }
     }
@@ -83,27 +91,22 @@ pub trait ResetCheckpointsModule:
     fn continue_modify_total_delegation_cap_step(
         &self,
         mut mdcap_data: ModifyTotalDelegationCapData<Self::Api>,
+        gas_threshold: u64,
     ) -> (OperationCompletionStatus, GlobalOpCheckpoint<Self::Api>) {
         match mdcap_data.step {
-            ModifyDelegationCapStep::ComputeAllRewards(car_data) => {
-                if let Some(more_computation) = self.compute_all_rewards(car_data) {
-                    mdcap_data.step = ModifyDelegationCapStep::ComputeAllRewards(more_computation);
-                    (
-                        OperationCompletionStatus::InterruptedBeforeOutOfGas,
-                        GlobalOpCheckpoint::ModifyTotalDelegationCap(mdcap_data),
-                    )
-                } else {
-                    mdcap_data.step = ModifyDelegationCapStep::SwapWaitingToActive;
-                    (
-                        OperationCompletionStatus::Completed,
-                        GlobalOpCheckpoint::ModifyTotalDelegationCap(mdcap_data),
-                    )
-                }
+            ModifyDelegationCapStep::ComputeAllRewards(_) => {
+                // only left over from before the reward index, nothing to compute anymore
+                self.update_reward_index();
+                mdcap_data.step = ModifyDelegationCapStep::SwapWaitingToActive;
+                (
+                    OperationCompletionStatus::Completed,
+                    GlobalOpCheckpoint::ModifyTotalDelegationCap(mdcap_data),
+                )
             }
             ModifyDelegationCapStep::SwapWaitingToActive => {
-                let _ = self.swap_waiting_to_active(
+                self.compute_rewards_and_swap_waiting_to_active(
                     &mut mdcap_data.remaining_swap_waiting_to_active, // decreases this field directly
-                    || self.blockchain().get_gas_left() < STOP_AT_GASLIMIT,
+                    || self.blockchain().get_gas_left() < gas_threshold,
                 );
                 if mdcap_data.remaining_swap_waiting_to_active > 0 {
                     (
@@ -111,6 +114,7 @@ pub trait ResetCheckpointsModule:
                         GlobalOpCheckpoint::ModifyTotalDelegationCap(mdcap_data),
                     )
                 } else {
+                    self.log_global_op_step(b"modifyTotalDelegationCap", b"swapWaitingToActive");
                     mdcap_data.step = ModifyDelegationCapStep::SwapUnstakedToDeferredPayment;
                     (
                         OperationCompletionStatus::Completed,
@@ -121,7 +125,7 @@ pub trait ResetCheckpointsModule:
             ModifyDelegationCapStep::SwapUnstakedToDeferredPayment => {
                 self.swap_unstaked_to_deferred_payment(
                     &mut mdcap_data.remaining_swap_unstaked_to_def_p, // decreases this field directly
-                    || self.blockchain().get_gas_left() < STOP_AT_GASLIMIT,
+                    || self.blockchain().get_gas_left() < gas_threshold,
                 );
                 if mdcap_data.remaining_swap_unstaked_to_def_p > 0 {
                     (
@@ -129,6 +133,10 @@ pub trait ResetCheckpointsModule:
                         GlobalOpCheckpoint::ModifyTotalDelegationCap(mdcap_data),
                     )
                 } else {
+                    self.log_global_op_step(
+                        b"modifyTotalDelegationCap",
+                        b"swapUnstakedToDeferredPayment",
+                    );
                     mdcap_data.step = ModifyDelegationCapStep::SwapActiveToDeferredPayment;
                     (
                         OperationCompletionStatus::Completed,
@@ -137,9 +145,9 @@ pub trait ResetCheckpointsModule:
                 }
             }
             ModifyDelegationCapStep::SwapActiveToDeferredPayment => {
-                self.swap_active_to_deferred_payment(
+                self.compute_rewards_and_swap_active_to_deferred_payment(
                     &mut mdcap_data.remaining_swap_active_to_def_p, // decreases this field directly
-                    || self.blockchain().get_gas_left() < STOP_AT_GASLIMIT,
+                    || self.blockchain().get_gas_left() < gas_threshold,
                 );
                 if mdcap_data.remaining_swap_active_to_def_p > 0 {
                     (
@@ -148,6 +156,11 @@ pub trait ResetCheckpointsModule:
                     )
                 } else {
                     // finish
+                    self.log_global_op_step(
+                        b"modifyTotalDelegationCap",
+                        b"swapActiveToDeferredPayment",
+                    );
+                    self.update_reward_index();
                     self.set_total_delegation_cap(mdcap_data.new_delegation_cap);
                     (
                         OperationCompletionStatus::Completed,
@@ -158,63 +171,137 @@ pub trait ResetCheckpointsModule:
         }
     }
 
-    /// When there is a change of the base cap from which the rewards are computed,
-    /// the checkpoints must be reset for all the delegators.
-    /// This process might be longer then one block - reaching the gaslimit
-    /// thus will do it by saving where it left before reaching out of gas.
-    /// No change in the delegators total cap is allowed before all the checkpoints are recalculated.
-    ///
-    /// Returns something if there is more computing to be done.
-    fn compute_all_rewards(
-        &self,
-        mut data: ComputeAllRewardsData<Self::Api>,
-    ) -> Option<ComputeAllRewardsData<Self::Api>> {
-        // if more rewards arrived since computation started,
-        // it must be restarted from scratch
-        let curr_rewards_checkpoint = self.get_total_cumulated_rewards();
-        if data.rewards_checkpoint != curr_rewards_checkpoint {
-            data.last_id = 0;
-            data.sum_unclaimed = BigUint::zero();
-            data.rewards_checkpoint = curr_rewards_checkpoint;
-        }
+    /// Cancels the global operation in progress, as long as it did not move any funds yet.
+    /// Changing the delegation cap can be aborted in any step up to the first swap:
+    /// the reward index was brought up to date with the old delegation cap when the operation started,
+    /// and the new cap is only set once the operation completes, so the rewards remain consistent.
+    /// Every swap breaks active + unstaked == delegation cap, until the new cap is set,
+    /// which is how funds that were already moved are detected.
+    /// Operations left over from before the reward index can still be aborted while computing rewards.
+    #[only_owner]
+    #[endpoint(abortGlobalOperation)]
+    fn abort_global_operation(&self) {
+        let operation: &[u8] = match self.global_op_checkpoint().get() {
+            GlobalOpCheckpoint::None => sc_panic!("no global operation in progress"),
+            GlobalOpCheckpoint::ModifyTotalDelegationCap(mdcap_data) => {
+                let is_abortable = match mdcap_data.step {
+                    ModifyDelegationCapStep::ComputeAllRewards(_) => true,
+                    _ => self.is_delegation_cap_filled_exactly(),
+                };
+                require!(is_abortable, "global operation can no longer be aborted");
+                b"modifyTotalDelegationCap"
+            }
+            GlobalOpCheckpoint::ChangeServiceFee { .. } => b"changeServiceFee",
+        };
 
-        let num_nodes = self.get_num_users();
+        self.global_op_checkpoint().clear();
+        self.global_op_aborted_event(&ManagedBuffer::from(operation));
+    }
 
-        while data.last_id < num_nodes {
-            if self.blockchain().get_gas_left() < STOP_AT_GASLIMIT {
-                return Some(data);
-            }
+    /// Active + unstaked stake add up to the delegation cap outside bootstrap mode.
+    fn is_delegation_cap_filled_exactly(&self) -> bool {
+        let total_active = self.get_user_stake_of_type(USER_STAKE_TOTALS_ID, FundType::Active);
+        let total_unstaked = self.get_user_stake_of_type(USER_STAKE_TOTALS_ID, FundType::UnStaked);
+        !self.is_bootstrap_mode()
+            && total_active + total_unstaked == self.get_total_delegation_cap()
+    }
 
-            let current_user_id = non_zero_usize_from_n_plus_1(data.last_id);
-            let user_data = self.load_updated_user_rewards(current_user_id);
-            self.store_user_reward_data(current_user_id, &user_data);
-            data.sum_unclaimed += user_data.unclaimed_rewards;
-            data.last_id = current_user_id.get();
+    /// Decodes the global operation checkpoint.
+    /// Users processed only advance while computing the rewards of all delegators,
+    /// the remaining swap amounts only decrease during their respective steps.
+    #[view(getGlobalOperationProgress)]
+    fn get_global_operation_progress(&self) -> GlobalOpProgress<Self::Api> {
+        let mut progress = GlobalOpProgress {
+            operation: ManagedBuffer::new(),
+            step: ManagedBuffer::new(),
+            users_processed: 0,
+            num_users: self.get_num_users(),
+            remaining_swap_waiting_to_active: BigUint::zero(),
+            remaining_swap_unstaked_to_def_p: BigUint::zero(),
+            remaining_swap_active_to_def_p: BigUint::zero(),
+        };
+
+        let orc = self.global_op_checkpoint().get();
+        let (operation, step) = self.global_op_step_names(&orc);
+        progress.operation = ManagedBuffer::from(operation);
+        progress.step = ManagedBuffer::from(step);
+        match orc {
+            GlobalOpCheckpoint::None => {}
+            GlobalOpCheckpoint::ModifyTotalDelegationCap(mdcap_data) => {
+                if let ModifyDelegationCapStep::ComputeAllRewards(car_data) = mdcap_data.step {
+                    progress.users_processed = car_data.last_id;
+                }
+                progress.remaining_swap_waiting_to_active =
+                    mdcap_data.remaining_swap_waiting_to_active;
+                progress.remaining_swap_unstaked_to_def_p =
+                    mdcap_data.remaining_swap_unstaked_to_def_p;
+                progress.remaining_swap_active_to_def_p = mdcap_data.remaining_swap_active_to_def_p;
+            }
+            GlobalOpCheckpoint::ChangeServiceFee {
+                compute_rewards_data,
+                ..
+            } => {
+                progress.users_processed = compute_rewards_data.last_id;
+            }
         }
 
-        // divisions are inexact so a small remainder can remain after distributing rewards
-        // give it to the owner, to keep things clear
-        let remainder =
-            &self.get_total_cumulated_rewards() - &data.sum_unclaimed - self.get_sent_rewards();
-        if remainder > 0 {
-            let mut node_unclaimed = self.get_user_rew_unclaimed(OWNER_USER_ID);
-            node_unclaimed += &remainder;
-            self.set_user_rew_unclaimed(OWNER_USER_ID, &node_unclaimed);
+        progress
+    }
+
+    /// Names of the operation and of the step the checkpoint is at, empty if none is in progress.
+    fn global_op_step_names(
+        &self,
+        orc: &GlobalOpCheckpoint<Self::Api>,
+    ) -> (&'static [u8], &'static [u8]) {
+        match orc {
+            GlobalOpCheckpoint::None => (b"", b""),
+            GlobalOpCheckpoint::ModifyTotalDelegationCap(mdcap_data) => {
+                let step: &'static [u8] = match mdcap_data.step {
+                    ModifyDelegationCapStep::ComputeAllRewards(_) => b"computeAllRewards",
+                    ModifyDelegationCapStep::SwapWaitingToActive => b"swapWaitingToActive",
+                    ModifyDelegationCapStep::SwapUnstakedToDeferredPayment => {
+                        b"swapUnstakedToDeferredPayment"
+                    }
+                    ModifyDelegationCapStep::SwapActiveToDeferredPayment => {
+                        b"swapActiveToDeferredPayment"
+                    }
+                };
+                (b"modifyTotalDelegationCap", step)
+            }
+            GlobalOpCheckpoint::ChangeServiceFee { .. } => {
+                (b"changeServiceFee", b"computeAllRewards")
+            }
         }
+    }
 
-        None
+    fn log_global_op_step(&self, operation: &[u8], step: &[u8]) {
+        self.global_op_step_completed_event(
+            &ManagedBuffer::from(operation),
+            &ManagedBuffer::from(step),
+        );
     }
 
-    /// Total delegation cap can be modified by owner only.
-    /// It will recalculate and set the checkpoint for all the delegators
-    #[only_owner]
+    /// Total delegation cap can be modified by owner or by fee managers only.
+    /// Rewards are brought up to date for the delegators whose stake gets swapped, and only for them.
+    /// Optionally, the gas threshold at which to interrupt can be overridden for this call.
     #[endpoint(modifyTotalDelegationCap)]
-    fn modify_total_delegation_cap(&self, new_total_cap: BigUint) -> OperationCompletionStatus {
+    fn modify_total_delegation_cap(
+        &self,
+        new_total_cap: BigUint,
+        opt_gas_threshold: OptionalValue<u64>,
+    ) -> OperationCompletionStatus {
+        self.require_role(Role::FeeManager);
+        let gas_threshold =
+            self.resolve_gas_threshold(self.get_global_op_gas_threshold(), opt_gas_threshold);
+
         require!(
             !self.is_global_op_in_progress(),
             "cannot modify total delegation cap when last is in progress"
         );
 
+        // rewards so far are split according to the old delegation cap
+        self.update_reward_index();
+
         let total_waiting = self.get_user_stake_of_type(USER_STAKE_TOTALS_ID, FundType::Waiting);
         let total_active = self.get_user_stake_of_type(USER_STAKE_TOTALS_ID, FundType::Active);
         let total_unstaked = self.get_user_stake_of_type(USER_STAKE_TOTALS_ID, FundType::UnStaked);
@@ -225,13 +312,11 @@ pub trait ResetCheckpointsModule:
             if new_total_cap > max_available {
                 // we remain in bootstrap mode
                 // and so nothing else to be done here:
-                // compute all rewards not necessary - no rewards yet
                 // swap not necessary - there cannot be any waiting or unstaked funds
                 self.set_total_delegation_cap(new_total_cap);
                 return OperationCompletionStatus::Completed;
             } else {
                 // bootstrap mode is over
-                // no rewards to compute, but
                 // swap might be necessary
                 self.set_bootstrap_mode(false);
 
@@ -270,9 +355,7 @@ pub trait ResetCheckpointsModule:
                     remaining_swap_waiting_to_active: swap_amount,
                     remaining_swap_active_to_def_p: BigUint::zero(),
                     remaining_swap_unstaked_to_def_p: BigUint::zero(),
-                    step: ModifyDelegationCapStep::ComputeAllRewards(ComputeAllRewardsData::new(
-                        self.get_total_cumulated_rewards(),
-                    )),
+                    step: ModifyDelegationCapStep::SwapWaitingToActive,
                 })
             }
             Ordering::Less => {
@@ -295,53 +378,102 @@ pub trait ResetCheckpointsModule:
                     swap_unstaked_to_def_p = total_unstaked;
                 }
 
+                // the liquid staking pool is never forced out, the rest of the active stake must cover the swap
+                require!(
+                    swap_active_to_def_p <= &total_active - &self.get_liquid_pool_active_stake(),
+                    "cannot decrease delegation cap below the tokenized stake"
+                );
+
                 GlobalOpCheckpoint::ModifyTotalDelegationCap(ModifyTotalDelegationCapData {
                     new_delegation_cap: new_total_cap,
                     remaining_swap_waiting_to_active: BigUint::zero(),
                     remaining_swap_active_to_def_p: swap_active_to_def_p,
                     remaining_swap_unstaked_to_def_p: swap_unstaked_to_def_p,
-                    step: ModifyDelegationCapStep::ComputeAllRewards(ComputeAllRewardsData::new(
-                        self.get_total_cumulated_rewards(),
-                    )),
+                    step: ModifyDelegationCapStep::SwapWaitingToActive,
                 })
             }
         };
 
-        self.continue_global_operation(orc)
+        self.continue_global_operation(orc, gas_threshold)
+    }
+
+    /// First step of changing the service fee: the new fee is announced,
+    /// and can only be applied with `setServiceFee` after the notice period.
+    /// Replaces any previous announcement.
+    #[endpoint(announceServiceFee)]
+    fn announce_service_fee(&self, service_fee_per_10000: usize) {
+        self.require_role(Role::FeeManager);
+
+        require!(
+            service_fee_per_10000 <= PERCENTAGE_DENOMINATOR,
+            "service fee out of range"
+        );
+        self.validate_service_fee_policy(service_fee_per_10000);
+
+        let effective_block_nonce =
+            self.blockchain().get_block_nonce() + self.service_fee_notice_blocks().get();
+        self.pending_service_fee().set(ServiceFeeAnnouncement {
+            service_fee: service_fee_per_10000,
+            effective_block_nonce,
+        });
+
+        self.service_fee_announced_event(service_fee_per_10000, effective_block_nonce);
+    }
+
+    #[endpoint(cancelServiceFeeAnnouncement)]
+    fn cancel_service_fee_announcement(&self) {
+        self.require_role(Role::FeeManager);
+
+        require!(
+            !self.pending_service_fee().is_empty(),
+            "no service fee change announced"
+        );
+
+        let announcement = self.pending_service_fee().take();
+        self.service_fee_announcement_cancelled_event(announcement.service_fee);
     }
 
-    /// The stake per node can be changed by the owner.
+    /// The service fee can be changed by the owner or by fee managers.
+    /// The new fee must have been announced with `announceServiceFee`, and the notice period must have passed.
     /// It does not get set in the contructor, so the owner has to manually set it after the contract is deployed.
     #[endpoint(setServiceFee)]
     fn set_service_fee_endpoint(&self, service_fee_per_10000: usize) -> OperationCompletionStatus {
+        self.require_role(Role::FeeManager);
+
         require!(
             service_fee_per_10000 <= PERCENTAGE_DENOMINATOR,
             "service fee out of range"
         );
+        self.validate_service_fee_policy(service_fee_per_10000);
 
         require!(
             !self.is_global_op_in_progress(),
             "global checkpoint is in progress"
         );
 
+        require!(
+            !self.pending_service_fee().is_empty(),
+            "service fee change not announced"
+        );
+        let announcement = self.pending_service_fee().get();
+        require!(
+            announcement.service_fee == service_fee_per_10000,
+            "service fee does not match the announced one"
+        );
+        require!(
+            self.blockchain().get_block_nonce() >= announcement.effective_block_nonce,
+            "service fee notice period not over"
+        );
+        self.pending_service_fee().clear();
+
         let new_service_fee = BigUint::from(service_fee_per_10000);
         if self.get_service_fee() == new_service_fee {
             return OperationCompletionStatus::Completed;
         }
 
-        if self.is_bootstrap_mode() {
-            // no rewards to compute
-            // change service fee directly
-            self.set_service_fee(new_service_fee);
-            OperationCompletionStatus::Completed
-        } else {
-            // start compute all rewards
-            self.continue_global_operation(GlobalOpCheckpoint::ChangeServiceFee {
-                new_service_fee,
-                compute_rewards_data: ComputeAllRewardsData::new(
-                    self.get_total_cumulated_rewards(),
-                ),
-            })
-        }
+        // rewards so far are split according to the old service fee
+        self.update_reward_index();
+        self.set_service_fee(new_service_fee);
+        OperationCompletionStatus::Completed
     }
 }
@@ -1,12 +1,17 @@
-use multiversx_sc::{api::ManagedTypeApi, types::BigUint};
+use multiversx_sc::{
+    api::ManagedTypeApi,
+    types::{BigUint, ManagedBuffer},
+};
 
 multiversx_sc::derive_imports!();
 
 /// Models any computation that can pause itself when it runs out of gas and continue in another block.
-#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, TypeAbi, PartialEq, Debug)]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, TypeAbi, Clone, PartialEq, Debug)]
 pub enum GlobalOpCheckpoint<M: ManagedTypeApi> {
     None,
     ModifyTotalDelegationCap(ModifyTotalDelegationCapData<M>),
+    /// No longer started, since the service fee changes right away.
+    /// Only kept to be able to finish operations started before the reward index was introduced.
     ChangeServiceFee {
         new_service_fee: BigUint<M>,
         compute_rewards_data: ComputeAllRewardsData<M>,
@@ -30,7 +35,7 @@ impl<M: ManagedTypeApi> GlobalOpCheckpoint<M> {
 }
 
 /// Contains data needed to be persisted while performing a change in the total delegation cap.
-#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, TypeAbi, PartialEq, Debug)]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, TypeAbi, Clone, PartialEq, Debug)]
 pub struct ModifyTotalDelegationCapData<M: ManagedTypeApi> {
     pub new_delegation_cap: BigUint<M>,
     pub remaining_swap_waiting_to_active: BigUint<M>,
@@ -40,8 +45,10 @@ pub struct ModifyTotalDelegationCapData<M: ManagedTypeApi> {
 }
 
 /// Models the steps that need to be executed when modifying the total delegation cap.
-#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, TypeAbi, PartialEq, Debug)]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, TypeAbi, Clone, PartialEq, Debug)]
 pub enum ModifyDelegationCapStep<M: ManagedTypeApi> {
+    /// No longer used, since rewards are only computed for the delegators whose stake gets swapped.
+    /// Only kept to be able to finish operations started before the reward index was introduced.
     ComputeAllRewards(ComputeAllRewardsData<M>),
     SwapWaitingToActive,
     SwapUnstakedToDeferredPayment,
@@ -49,7 +56,7 @@ pub enum ModifyDelegationCapStep<M: ManagedTypeApi> {
 }
 
 /// Models the interrupted state of compute_all_rewards.
-#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, TypeAbi, PartialEq, Debug)]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, TypeAbi, Clone, PartialEq, Debug)]
 pub struct ComputeAllRewardsData<M: ManagedTypeApi> {
     pub last_id: usize,
     pub sum_unclaimed: BigUint<M>,
@@ -65,3 +72,17 @@ impl<M: ManagedTypeApi> ComputeAllRewardsData<M> {
         }
     }
 }
+
+/// The global operation in progress, decoded into progress numbers.
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, TypeAbi, Clone, PartialEq, Debug)]
+pub struct GlobalOpProgress<M: ManagedTypeApi> {
+    /// Empty if no operation is in progress.
+    pub operation: ManagedBuffer<M>,
+    pub step: ManagedBuffer<M>,
+    /// Only relevant while computing the rewards of all delegators.
+    pub users_processed: usize,
+    pub num_users: usize,
+    pub remaining_swap_waiting_to_active: BigUint<M>,
+    pub remaining_swap_unstaked_to_def_p: BigUint<M>,
+    pub remaining_swap_active_to_def_p: BigUint<M>,
+}
@@ -7,10 +7,13 @@ pub trait RewardEndpointsModule:
     crate::settings::SettingsModule
     + crate::rewards_state::RewardStateModule
     + crate::reset_checkpoint_state::ResetCheckpointStateModule
+    + crate::liquid_staking_state::LiquidStakingStateModule
+    + crate::user_stake_state::UserStakeStateModule
     + crate::events::EventsModule
     + user_fund_storage::user_data::UserDataModule
     + user_fund_storage::fund_module::FundModule
     + user_fund_storage::fund_view_module::FundViewModule
+    + user_fund_storage::fund_transf_module::FundTransformationsModule
     + multiversx_sc_modules::features::FeaturesModule
     + multiversx_sc_modules::pause::PauseModule
 {
@@ -32,6 +35,7 @@ pub trait RewardEndpointsModule:
             "claim rewards is temporarily paused as checkpoint is reset"
         );
 
+        self.update_reward_index();
         let mut user_data = self.load_updated_user_rewards(user_id);
 
         if user_data.unclaimed_rewards > 0 {
@@ -45,13 +49,137 @@ pub trait RewardEndpointsModule:
         self.store_user_reward_data(user_id, &user_data);
     }
 
+    /// Sends part or all of the rewards to which the caller is entitled to another address.
+    /// The rest of the rewards remain in the contract, and can be claimed later.
+    #[endpoint(claimRewardsTo)]
+    fn claim_rewards_to(&self, amount: BigUint, destination: ManagedAddress) {
+        require!(self.not_paused(), "contract paused");
+        self.check_feature_on(b"claimRewards", true);
+
+        let caller = self.blockchain().get_caller();
+        let user_id = NonZeroUsize::new(self.get_user_id(&caller))
+            .unwrap_or_else(|| sc_panic!("unknown caller"));
+
+        require!(
+            !self.is_global_op_in_progress(),
+            "claim rewards is temporarily paused as checkpoint is reset"
+        );
+
+        require!(amount > 0, "cannot claim zero rewards");
+        require!(
+            destination != self.blockchain().get_sc_address(),
+            "cannot claim rewards to the contract itself"
+        );
+
+        self.update_reward_index();
+        let mut user_data = self.load_updated_user_rewards(user_id);
+        require!(
+            amount <= user_data.unclaimed_rewards,
+            "cannot claim more than the available rewards"
+        );
+
+        self.claim_rewards_to_event(&caller, &destination, &amount);
+
+        self.send_rewards(&destination, &amount);
+
+        user_data.unclaimed_rewards -= &amount;
+
+        self.store_user_reward_data(user_id, &user_data);
+    }
+
+    /// Converts the rewards to which the caller is entitled into new stake.
+    /// The rewards never leave the contract, they go straight into the waiting list,
+    /// exactly as if they had been claimed and then staked again.
+    #[endpoint(reDelegateRewards)]
+    fn redelegate_rewards(&self) {
+        require!(self.not_paused(), "contract paused");
+        self.check_feature_on(b"reDelegateRewards", true);
+
+        let caller = self.blockchain().get_caller();
+        let user_id = NonZeroUsize::new(self.get_user_id(&caller))
+            .unwrap_or_else(|| sc_panic!("unknown caller"));
+
+        require!(
+            !self.is_global_op_in_progress(),
+            "redelegate rewards is temporarily paused as checkpoint is reset"
+        );
+
+        self.update_reward_index();
+        let mut user_data = self.load_updated_user_rewards(user_id);
+        require!(user_data.unclaimed_rewards > 0, "no rewards to redelegate");
+
+        let rewards = core::mem::replace(&mut user_data.unclaimed_rewards, BigUint::zero());
+
+        // must be saved before staking, since staking can recompute rewards for this user
+        self.store_user_reward_data(user_id, &user_data);
+
+        self.redelegate_rewards_event(&caller, &rewards);
+
+        // from an accounting point of view, the rewards were sent out and then staked back in
+        self.increase_sent_rewards(&rewards);
+        self.process_stake(rewards);
+
+        // only the resulting position needs to respect the minimum stake, not the rewards themselves
+        self.validate_user_minimum_stake(user_id.get());
+    }
+
+    /// Raw id of the last user reached by the auto-compound batch pass.
+    #[view(autoCompoundCheckpoint)]
+    #[storage_mapper("auto_compound_checkpoint")]
+    fn auto_compound_checkpoint(&self) -> SingleValueMapper<usize>;
+
+    /// Brings rewards up to date for all users, which compounds them for those who opted in.
+    /// Will stop if running low on gas, and continue from where it left off when called again.
+    /// Does not block the rest of the contract, since each user is processed individually.
+    /// Optionally, the gas threshold at which to stop can be overridden for this call.
+    #[only_owner]
+    #[endpoint(autoCompoundRewards)]
+    fn auto_compound_rewards(
+        &self,
+        opt_gas_threshold: OptionalValue<u64>,
+    ) -> OperationCompletionStatus {
+        require!(self.not_paused(), "contract paused");
+        let gas_threshold =
+            self.resolve_gas_threshold(self.get_auto_compound_gas_threshold(), opt_gas_threshold);
+
+        require!(
+            !self.is_global_op_in_progress(),
+            "contract is temporarily paused as checkpoint is reset"
+        );
+
+        // reserve half of the gas for the subsequent swap Waiting -> Active
+        let reserved_gas = self.blockchain().get_gas_left() / 2 + gas_threshold;
+
+        let num_users = self.get_num_users();
+        let mut last_id = self.auto_compound_checkpoint().get();
+        while last_id < num_users {
+            if self.blockchain().get_gas_left() < reserved_gas {
+                break;
+            }
+
+            let current_user_id = non_zero_usize_from_n_plus_1(last_id);
+            if self.user_auto_compound(current_user_id.get()).get() {
+                self.compute_one_user_reward(current_user_id);
+            }
+            last_id = current_user_id.get();
+        }
+
+        // move funds around
+        self.use_waiting_to_replace_unstaked();
+
+        if last_id < num_users {
+            self.auto_compound_checkpoint().set(last_id);
+            OperationCompletionStatus::InterruptedBeforeOutOfGas
+        } else {
+            self.auto_compound_checkpoint().clear();
+            OperationCompletionStatus::Completed
+        }
+    }
+
     fn send_rewards(&self, to: &ManagedAddress, amount: &BigUint) {
         // send funds
         self.send().direct_egld(to, amount);
 
-        // increment globally sent funds
-        let mut sent_rewards = self.get_sent_rewards();
-        sent_rewards += amount;
-        self.set_sent_rewards(&sent_rewards);
+        self.increase_sent_rewards(amount);
     }
 }
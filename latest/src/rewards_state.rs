@@ -5,16 +5,51 @@ use user_fund_storage::fund_view_module::USER_STAKE_TOTALS_ID;
 use user_fund_storage::types::FundType;
 
 multiversx_sc::imports!();
+multiversx_sc::derive_imports!();
+
+/// The reward index is scaled by this factor, so that rounding errors stay negligible.
+pub const REWARD_INDEX_PRECISION: u128 = 1_000_000_000_000_000_000_000_000_000_000_000_000;
 
 // Groups together data per delegator from the storage.
 pub struct UserRewardData<BigUint> {
     /// The value of the total cumulated rewards in the contract when the user's rewards were computed the last time.
+    /// Only relevant for rewards from before the reward index was introduced.
     pub reward_checkpoint: BigUint,
 
+    /// The value of the reward index when the user's rewards were computed the last time.
+    pub reward_index: BigUint,
+
     /// Rewards that are computed but not yet sent to the delegator.
     pub unclaimed_rewards: BigUint,
 }
 
+/// The global reward index, brought up to date with the rewards that arrived since it was last stored.
+pub struct RewardIndexData<BigUint> {
+    /// Cumulated delegator rewards per unit of delegation cap, times `REWARD_INDEX_PRECISION`.
+    pub reward_index: BigUint,
+
+    /// The value of the total cumulated rewards in the contract when the index was last updated.
+    pub rewards_checkpoint: BigUint,
+
+    /// Rewards owed to the owner since the index was last stored:
+    /// the service fee, plus the rewards of the delegation cap not covered by active stake.
+    pub owner_new_rewards: BigUint,
+
+    /// The part of the service fee that goes to the keeper pool, since the index was last stored.
+    pub keeper_new_rewards: BigUint,
+}
+
+/// Reward parameters at the moment the reward index was introduced.
+/// Rewards that arrived before it are computed for each delegator the old way, with these values,
+/// the first time the delegator's rewards are computed afterwards.
+#[derive(TopEncode, TopDecode, TypeAbi, PartialEq, Debug)]
+pub struct LegacyRewardsSnapshot<M: ManagedTypeApi> {
+    pub total_cumulated_rewards: BigUint<M>,
+    pub service_fee: BigUint<M>,
+    pub total_delegation_cap: BigUint<M>,
+    pub total_active_stake: BigUint<M>,
+}
+
 /// Contains logic to compute and distribute individual delegator rewards.
 #[multiversx_sc::derive::module]
 pub trait RewardStateModule:
@@ -22,6 +57,8 @@ pub trait RewardStateModule:
     + user_fund_storage::user_data::UserDataModule
     + user_fund_storage::fund_module::FundModule
     + user_fund_storage::fund_view_module::FundViewModule
+    + user_fund_storage::fund_transf_module::FundTransformationsModule
+    + crate::events::EventsModule
 {
     /// Claiming rewards has 2 steps:
     /// 1. computing the delegator rewards out of the total rewards, and
@@ -42,18 +79,65 @@ pub trait RewardStateModule:
     /// when the user last claimed their own personal rewards.
     /// If zero, it means the user never claimed any rewards.
     /// If equal to get_total_cumulated_rewards, it means the user claimed everything there is for him/her.
+    /// Since the reward index was introduced, only used to settle the rewards from before it.
     #[storage_get("u_rew_checkp")]
     fn get_user_rew_checkpoint(&self, user_id: NonZeroUsize) -> BigUint;
 
     #[storage_set("u_rew_checkp")]
     fn set_user_rew_checkpoint(&self, user_id: NonZeroUsize, user_rew_checkpoint: &BigUint);
 
+    /// Cumulated delegator rewards per unit of delegation cap, times `REWARD_INDEX_PRECISION`.
+    /// Only updated when rewards are computed, check `getRewardIndex` for the up to date value.
+    #[storage_mapper("reward_index")]
+    fn reward_index(&self) -> SingleValueMapper<BigUint>;
+
+    /// The value of the total cumulated rewards in the contract when the reward index was last updated.
+    #[storage_mapper("reward_index_checkp")]
+    fn reward_index_checkpoint(&self) -> SingleValueMapper<BigUint>;
+
+    /// The value of the reward index when the user's rewards were computed the last time.
+    #[storage_mapper("u_rew_index")]
+    fn user_reward_index(&self, user_id: NonZeroUsize) -> SingleValueMapper<BigUint>;
+
+    #[view(getLegacyRewardsSnapshot)]
+    #[storage_mapper("legacy_rewards_snapshot")]
+    fn legacy_rewards_snapshot(&self) -> SingleValueMapper<LegacyRewardsSnapshot<Self::Api>>;
+
     #[storage_get("sent_rewards")]
     fn get_sent_rewards(&self) -> BigUint;
 
     #[storage_set("sent_rewards")]
     fn set_sent_rewards(&self, sent_rewards: &BigUint);
 
+    fn increase_sent_rewards(&self, amount: &BigUint) {
+        // increment globally sent funds
+        let mut sent_rewards = self.get_sent_rewards();
+        sent_rewards += amount;
+        self.set_sent_rewards(&sent_rewards);
+    }
+
+    /// Delegators can opt in to have their rewards converted into new stake
+    /// every time their rewards are brought up to date.
+    #[storage_mapper("u_auto_compound")]
+    fn user_auto_compound(&self, user_id: usize) -> SingleValueMapper<bool>;
+
+    /// Called once, when the contract is deployed or upgraded to a version with a reward index.
+    /// Rewards up to this point are left to be computed the old way, everything after goes through the index.
+    fn init_reward_index(&self) {
+        if !self.legacy_rewards_snapshot().is_empty() {
+            return;
+        }
+
+        let total_cumulated_rewards = self.get_total_cumulated_rewards();
+        self.reward_index_checkpoint().set(&total_cumulated_rewards);
+        self.legacy_rewards_snapshot().set(&LegacyRewardsSnapshot {
+            total_cumulated_rewards,
+            service_fee: self.get_service_fee(),
+            total_delegation_cap: self.get_total_delegation_cap(),
+            total_active_stake: self.get_user_stake_of_type(USER_STAKE_TOTALS_ID, FundType::Active),
+        });
+    }
+
     /// Yields all the rewards received by the contract since its creation.
     /// This value is monotonously increasing - it can never decrease.
     /// Handing out rewards will not decrease this value.
@@ -73,8 +157,11 @@ pub trait RewardStateModule:
     /// Both results are rounded down,
     /// so te rounding error is not in the result.
     /// This is deliberate, to avoid a very subtle rounding error edge case.
-    fn split_service_reward(&self, tot_rewards: &BigUint) -> (BigUint, BigUint) {
-        let service_fee = &self.get_service_fee();
+    fn split_service_reward(
+        &self,
+        tot_rewards: &BigUint,
+        service_fee: &BigUint,
+    ) -> (BigUint, BigUint) {
         let perc_denominator = &BigUint::from(PERCENTAGE_DENOMINATOR);
 
         // part of the rewards that goes to the owner
@@ -89,23 +176,141 @@ pub trait RewardStateModule:
         (service_rewards, total_delegators_rewards)
     }
 
+    /// Does not update storage, only returns the reward index as it would be after adding the new rewards.
+    ///
+    /// The new delegator rewards are spread over the entire delegation cap.
+    /// The part corresponding to the active stake goes into the index,
+    /// the part corresponding to the missing active stake (unstaked) goes to the owner, together with the service fee.
+    fn load_updated_reward_index(&self) -> RewardIndexData<BigUint> {
+        let mut index_data = RewardIndexData {
+            reward_index: self.reward_index().get(),
+            rewards_checkpoint: self.reward_index_checkpoint().get(),
+            owner_new_rewards: BigUint::zero(),
+            keeper_new_rewards: BigUint::zero(),
+        };
+
+        let tot_cumul_rewards = self.get_total_cumulated_rewards();
+        if tot_cumul_rewards <= index_data.rewards_checkpoint {
+            return index_data; // no new rewards
+        }
+
+        let tot_new_rewards = &tot_cumul_rewards - &index_data.rewards_checkpoint;
+        let (service_rewards, total_delegators_rewards) =
+            self.split_service_reward(&tot_new_rewards, &self.get_service_fee());
+        let keeper_service_fee_share = self.keeper_service_fee_share().get();
+        if keeper_service_fee_share > 0 {
+            index_data.keeper_new_rewards = &service_rewards
+                * &BigUint::from(keeper_service_fee_share)
+                / &BigUint::from(PERCENTAGE_DENOMINATOR);
+        }
+        index_data.owner_new_rewards = service_rewards - &index_data.keeper_new_rewards;
+
+        // while a delegation cap increase is in progress, the active stake can temporarily exceed the old cap
+        let tot_stake_active = self.get_user_stake_of_type(USER_STAKE_TOTALS_ID, FundType::Active);
+        let mut delegation_cap = self.get_total_delegation_cap();
+        if delegation_cap < tot_stake_active {
+            delegation_cap = tot_stake_active.clone();
+        }
+
+        if delegation_cap == 0 {
+            index_data.owner_new_rewards += total_delegators_rewards;
+        } else {
+            if tot_stake_active < delegation_cap {
+                let missing_stake = &delegation_cap - &tot_stake_active;
+                index_data.owner_new_rewards +=
+                    &total_delegators_rewards * &missing_stake / &delegation_cap;
+            }
+
+            index_data.reward_index +=
+                total_delegators_rewards * BigUint::from(REWARD_INDEX_PRECISION) / delegation_cap;
+        }
+
+        index_data.rewards_checkpoint = tot_cumul_rewards;
+        index_data
+    }
+
+    /// Brings the reward index up to date. Cheap, it does not depend on the number of delegators.
+    /// Must be called before anything that affects how rewards are split changes:
+    /// the service fee, the total delegation cap or the total active stake.
+    fn update_reward_index(&self) {
+        let index_data = self.load_updated_reward_index();
+        if index_data.rewards_checkpoint == self.reward_index_checkpoint().get() {
+            return; // nothing changed
+        }
+
+        if index_data.owner_new_rewards > 0 {
+            let mut owner_unclaimed = self.get_user_rew_unclaimed(OWNER_USER_ID);
+            owner_unclaimed += &index_data.owner_new_rewards;
+            self.set_user_rew_unclaimed(OWNER_USER_ID, &owner_unclaimed);
+        }
+        if index_data.keeper_new_rewards > 0 {
+            // from here on, these funds are accounted for by the keeper pool, not by the rewards
+            self.keeper_pool()
+                .update(|keeper_pool| *keeper_pool += &index_data.keeper_new_rewards);
+            self.increase_sent_rewards(&index_data.keeper_new_rewards);
+        }
+        self.reward_index().set(&index_data.reward_index);
+        self.reward_index_checkpoint()
+            .set(&index_data.rewards_checkpoint);
+    }
+
+    #[view(getRewardIndex)]
+    fn get_reward_index(&self) -> BigUint {
+        self.load_updated_reward_index().reward_index
+    }
+
     /// Does not update storage, only returns the user rewards object, after computing rewards.
+    /// When called from an endpoint, the reward index needs to be updated beforehand,
+    /// otherwise the new owner rewards would be counted twice.
     fn load_updated_user_rewards(&self, user_id: NonZeroUsize) -> UserRewardData<BigUint> {
         let mut user_data = self.load_user_reward_data(user_id);
+        self.add_legacy_rewards(user_id, &mut user_data);
 
-        // new rewards are what was added since the last time rewards were computed
-        let tot_cumul_rewards = self.get_total_cumulated_rewards();
-        let tot_new_rewards = &tot_cumul_rewards - &user_data.reward_checkpoint;
-        if tot_new_rewards == 0 {
-            return user_data; // nothing happened since the last claim
+        let index_data = self.load_updated_reward_index();
+        if user_id == OWNER_USER_ID {
+            // only non-zero if the index was not updated, i.e. in views
+            user_data.unclaimed_rewards += &index_data.owner_new_rewards;
+        }
+
+        // update delegator rewards based on Active stake
+        // delegator reward is: user stake * (reward index - user reward index) / REWARD_INDEX_PRECISION
+        if index_data.reward_index > user_data.reward_index {
+            let u_stake_active = self.get_user_stake_of_type(user_id.get(), FundType::Active);
+            if u_stake_active > 0 {
+                let index_diff = &index_data.reward_index - &user_data.reward_index;
+                user_data.unclaimed_rewards +=
+                    u_stake_active * index_diff / BigUint::from(REWARD_INDEX_PRECISION);
+            }
+        }
+
+        // update user data checkpoint
+        user_data.reward_index = index_data.reward_index;
+
+        user_data
+    }
+
+    /// Rewards from before the reward index was introduced.
+    /// Before that, the rewards of all delegators were recomputed whenever the service fee or the delegation cap changed,
+    /// so the values in the snapshot apply to the entire period since each delegator's last checkpoint.
+    fn add_legacy_rewards(&self, user_id: NonZeroUsize, user_data: &mut UserRewardData<BigUint>) {
+        if self.legacy_rewards_snapshot().is_empty() {
+            return;
+        }
+        let snapshot = self.legacy_rewards_snapshot().get();
+        if user_data.reward_checkpoint >= snapshot.total_cumulated_rewards {
+            return; // already accounted for
+        }
+
+        let tot_new_rewards = &snapshot.total_cumulated_rewards - &user_data.reward_checkpoint;
+        user_data.reward_checkpoint = snapshot.total_cumulated_rewards;
+        if snapshot.total_delegation_cap == 0 {
+            return;
         }
 
         // the owner is entitled to: tot_new_rewards * service_fee / NODE_DENOMINATOR
         // delegators are entitled to: tot_new_rewards * (1 - service_fee / NODE_DENOMINATOR)
         let (service_rewards, total_delegators_rewards) =
-            self.split_service_reward(&tot_new_rewards);
-
-        let delegation_cap = self.get_total_delegation_cap();
+            self.split_service_reward(&tot_new_rewards, &snapshot.service_fee);
 
         // update node rewards, if applicable
         if user_id == OWNER_USER_ID {
@@ -113,13 +318,11 @@ pub trait RewardStateModule:
             user_data.unclaimed_rewards += &service_rewards;
 
             // the owner gets the rewards for the missing active (unstaked) stake
-            let tot_stake_active =
-                self.get_user_stake_of_type(USER_STAKE_TOTALS_ID, FundType::Active);
-            if tot_stake_active < delegation_cap {
-                let missing_stake = &delegation_cap - &tot_stake_active;
+            if snapshot.total_active_stake < snapshot.total_delegation_cap {
+                let missing_stake = &snapshot.total_delegation_cap - &snapshot.total_active_stake;
                 let mut owner_unstaked_rewards = total_delegators_rewards.clone();
                 owner_unstaked_rewards *= &missing_stake;
-                owner_unstaked_rewards /= &delegation_cap;
+                owner_unstaked_rewards /= &snapshot.total_delegation_cap;
                 user_data.unclaimed_rewards += &owner_unstaked_rewards;
             }
         }
@@ -131,22 +334,73 @@ pub trait RewardStateModule:
             // total new rewards * (1 - service_fee / NODE_DENOMINATOR) * user stake / total delegation cap
             let mut delegator_new_rewards = total_delegators_rewards;
             delegator_new_rewards *= &u_stake_active;
-            delegator_new_rewards /= &delegation_cap;
+            delegator_new_rewards /= &snapshot.total_delegation_cap;
             user_data.unclaimed_rewards += &delegator_new_rewards;
         }
-
-        // update user data checkpoint
-        user_data.reward_checkpoint = tot_cumul_rewards;
-
-        user_data
     }
 
     /// Convenience method, brings user rewards up to date for one user.
+    /// Also compounds the rewards, if the user opted in for it.
     fn compute_one_user_reward(&self, user_id: NonZeroUsize) {
+        self.update_reward_index();
+        let mut user_data = self.load_updated_user_rewards(user_id);
+        self.auto_compound_if_enabled(user_id, &mut user_data);
+        self.store_user_reward_data(user_id, &user_data);
+    }
+
+    /// Same as `compute_one_user_reward`, but never compounds.
+    /// Used right before moving the stake of a user in the middle of a swap, when no fund items can be created,
+    /// and before stake leaves a user, where compounding would put part of it right back.
+    /// Compounding happens the next time the rewards of the user are computed.
+    fn settle_one_user_reward(&self, user_id: NonZeroUsize) {
+        self.update_reward_index();
         let user_data = self.load_updated_user_rewards(user_id);
         self.store_user_reward_data(user_id, &user_data);
     }
 
+    /// Moves the unclaimed rewards into the waiting list, if the user opted in for it.
+    /// The new stake is not activated here, because this can happen in the middle of a swap.
+    /// It will be activated by the next operation that moves funds around.
+    fn auto_compound_if_enabled(
+        &self,
+        user_id: NonZeroUsize,
+        user_data: &mut UserRewardData<BigUint>,
+    ) {
+        if !self.user_auto_compound(user_id.get()).get() {
+            return;
+        }
+
+        if user_data.unclaimed_rewards == 0
+            || user_data.unclaimed_rewards < self.get_auto_compound_min_amount()
+        {
+            return;
+        }
+
+        // addresses removed from the allow list keep their rewards claimable instead
+        let user_address = self.get_user_address(user_id.get());
+        if !self.can_stake(&user_address) {
+            return;
+        }
+
+        // compounding must not take the user over the maximum stake either
+        let maximum_stake = self.get_maximum_user_stake();
+        if maximum_stake > 0 && user_id != OWNER_USER_ID {
+            let relevant_stake = self.get_user_stake_of_type(user_id.get(), FundType::Waiting)
+                + self.get_user_stake_of_type(user_id.get(), FundType::Active);
+            if relevant_stake + &user_data.unclaimed_rewards > maximum_stake {
+                return;
+            }
+        }
+
+        let rewards = core::mem::replace(&mut user_data.unclaimed_rewards, BigUint::zero());
+
+        self.auto_compound_rewards_event(&user_address, &rewards);
+
+        // from an accounting point of view, the rewards were sent out and then staked back in
+        self.increase_sent_rewards(&rewards);
+        self.create_waiting(user_id.get(), rewards);
+    }
+
     /// Yields how much a user is able to claim in rewards at the present time.
     /// Does not update storage.
     #[view(getClaimableRewards)]
@@ -174,12 +428,34 @@ pub trait RewardStateModule:
         sum_unclaimed
     }
 
+    /// Paginated version of `getTotalUnclaimedRewards`. Pages start with a user id.
+    /// Yields the id where the next page starts (0 if there are no more pages),
+    /// followed by the sum of unclaimed rewards of the users in the current page.
+    #[view(getTotalUnclaimedRewardsPage)]
+    fn get_total_unclaimed_rewards_page(
+        &self,
+        start_id: usize,
+        limit: usize,
+    ) -> MultiValue2<usize, BigUint> {
+        let (user_ids, next_id) = self.user_id_page(start_id, limit);
+        let mut sum_unclaimed = BigUint::zero();
+        for user_id in user_ids {
+            let user_id_nz = NonZeroUsize::new(user_id).unwrap_or_else(|| sc_panic!("bad user_id"));
+            let user_data = self.load_updated_user_rewards(user_id_nz);
+            sum_unclaimed += user_data.unclaimed_rewards;
+        }
+
+        (next_id, sum_unclaimed).into()
+    }
+
     /// Loads the entire UserRewardData object from storage.
     fn load_user_reward_data(&self, user_id: NonZeroUsize) -> UserRewardData<BigUint> {
         let u_rew_checkp = self.get_user_rew_checkpoint(user_id);
+        let u_rew_index = self.user_reward_index(user_id).get();
         let u_rew_unclmd = self.get_user_rew_unclaimed(user_id);
         UserRewardData {
             reward_checkpoint: u_rew_checkp,
+            reward_index: u_rew_index,
             unclaimed_rewards: u_rew_unclmd,
         }
     }
@@ -187,6 +463,7 @@ pub trait RewardStateModule:
     /// Saves a UserRewardData object to storage.
     fn store_user_reward_data(&self, user_id: NonZeroUsize, data: &UserRewardData<BigUint>) {
         self.set_user_rew_checkpoint(user_id, &data.reward_checkpoint);
+        self.user_reward_index(user_id).set(&data.reward_index);
         self.set_user_rew_unclaimed(user_id, &data.unclaimed_rewards);
     }
 
@@ -208,6 +485,7 @@ pub trait RewardStateModule:
         unprotected -= total_waiting;
         unprotected -= total_deferred;
         unprotected -= total_withdraw;
+        unprotected -= self.keeper_pool().get();
         unprotected
     }
 }
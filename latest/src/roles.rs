@@ -0,0 +1,70 @@
+multiversx_sc::imports!();
+multiversx_sc::derive_imports!();
+
+/// Groups of operational endpoints that the owner can delegate to other addresses.
+/// The owner implicitly has all roles.
+#[derive(
+    TopEncode, TopDecode, NestedEncode, NestedDecode, TypeAbi, Clone, Copy, PartialEq, Debug,
+)]
+pub enum Role {
+    /// Staking, unstaking, unbonding and unjailing nodes, dust cleanup.
+    NodeOperator,
+    /// Service fee and total delegation cap.
+    FeeManager,
+    /// Pausing the contract. Unpausing is left to the owner.
+    Pauser,
+    /// Unbonding due nodes and dust cleanup below the keeper dust limit, for the keeper bounty.
+    Keeper,
+}
+
+#[multiversx_sc::derive::module]
+pub trait RolesModule:
+    crate::events::EventsModule + multiversx_sc_modules::pause::PauseModule
+{
+    #[storage_mapper("role")]
+    fn role_granted(&self, role: Role, address: &ManagedAddress) -> SingleValueMapper<bool>;
+
+    #[only_owner]
+    #[endpoint(grantRole)]
+    fn grant_role(&self, role: Role, address: ManagedAddress) {
+        self.role_granted(role, &address).set(true);
+        self.grant_role_event(&role, &address);
+    }
+
+    #[only_owner]
+    #[endpoint(revokeRole)]
+    fn revoke_role(&self, role: Role, address: ManagedAddress) {
+        self.role_granted(role, &address).clear();
+        self.revoke_role_event(&role, &address);
+    }
+
+    #[view(hasRole)]
+    fn has_role(&self, role: Role, address: &ManagedAddress) -> bool {
+        address == &self.blockchain().get_owner_address() || self.role_granted(role, address).get()
+    }
+
+    fn require_role(&self, role: Role) {
+        let caller = self.blockchain().get_caller();
+        require!(
+            self.has_role(role, &caller),
+            "caller is missing the required role"
+        );
+    }
+
+    /// Same as `require_role`, but any of the given roles will do.
+    fn require_any_role(&self, roles: &[Role]) {
+        let caller = self.blockchain().get_caller();
+        require!(
+            roles.iter().any(|&role| self.has_role(role, &caller)),
+            "caller is missing the required role"
+        );
+    }
+
+    /// Same as `pause`, but can also be called by pausers.
+    /// Only the owner can unpause.
+    #[endpoint(pauseContract)]
+    fn pause_contract(&self) {
+        self.require_role(Role::Pauser);
+        self.set_paused(true);
+    }
+}
@@ -1,6 +1,8 @@
 use core::num::NonZeroUsize;
+use user_fund_storage::fund_transf_module::WAITING_POLICY_WINDOW_SIZE;
 
 multiversx_sc::imports!();
+multiversx_sc::derive_imports!();
 
 /// Indicates how we express the percentage of rewards that go to the node.
 /// Since we cannot have floating point numbers, we use fixed point with this denominator.
@@ -11,10 +13,54 @@ pub static PERCENTAGE_DENOMINATOR: usize = 10000;
 /// This can also count as a delegator (if the owner adds stake into the contract) or not.
 pub static OWNER_USER_ID: NonZeroUsize = unsafe { NonZeroUsize::new_unchecked(1) };
 
+/// Global operations (`continueGlobalOperation`, `modifyTotalDelegationCap`) interrupt themselves
+/// when the gas left drops below this, unless configured otherwise.
+pub const DEFAULT_GLOBAL_OP_GAS_THRESHOLD: u64 = 100_000_000;
+
+/// Default gas threshold of the dust cleanup endpoints.
+pub const DEFAULT_DUST_CLEANUP_GAS_THRESHOLD: u64 = 20_000_000;
+
+/// Default gas threshold of `unBond` and `withdrawWaiting`.
+pub const DEFAULT_UNBOND_GAS_THRESHOLD: u64 = 50_000_000;
+
+/// Default gas threshold of `autoCompoundRewards`, on top of the half of the gas reserved for the swap.
+pub const DEFAULT_AUTO_COMPOUND_GAS_THRESHOLD: u64 = 20_000_000;
+
+/// Notice period of service fee changes set on deploy, about one day of 6 second blocks.
+/// It can be increased with `setServiceFeeNoticeBlocks`, but never decreased.
+pub const DEFAULT_SERVICE_FEE_NOTICE_BLOCKS: u64 = 14_400;
+
+/// No gas threshold can be lower than this, so that there is always enough gas left
+/// to save the progress of an interrupted operation.
+pub const MIN_GAS_THRESHOLD: u64 = 5_000_000;
+
+/// A service fee change that was announced, but cannot be applied yet.
+#[derive(TopEncode, TopDecode, TypeAbi, PartialEq, Debug)]
+pub struct ServiceFeeAnnouncement {
+    pub service_fee: usize,
+    pub effective_block_nonce: u64,
+}
+
+/// The order in which waiting stake is activated, when there is room for it.
+#[derive(TopEncode, TopDecode, TypeAbi, PartialEq, Clone, Copy, Debug)]
+pub enum WaitingActivationPolicy {
+    /// Waiting list entries are activated in the order they were created. The default.
+    FirstInFirstOut,
+    /// The largest waiting list entries are activated first, looking at the front of the waiting list only:
+    /// a larger entry further back waits until it is within `getWaitingPolicyWindowSize` entries of the front.
+    LargestFirst,
+    /// All waiting list entries are partially activated, in proportion to their size,
+    /// going through the waiting list a bounded number of entries at a time.
+    ProRata,
+    /// The owner's waiting stake is activated first, then the rest, in order.
+    /// Helps keep the owner stake share above the minimum.
+    OwnerFirst,
+}
+
 /// The module deals with initializaton and the global contract settings.
 ///
 #[multiversx_sc::derive::module]
-pub trait SettingsModule {
+pub trait SettingsModule: user_fund_storage::user_data::UserDataModule {
     /// Yields the address of the contract with which staking will be performed.
     /// This address is standard in the protocol, but it is saved in storage to avoid hardcoding it.
     #[view(getAuctionContractAddress)]
@@ -33,6 +79,113 @@ pub trait SettingsModule {
     #[storage_set("service_fee")]
     fn set_service_fee(&self, service_fee: BigUint);
 
+    /// Lowest service fee that can be set. 10000 = 100%.
+    #[view(getMinServiceFee)]
+    #[storage_mapper("min_service_fee")]
+    fn min_service_fee(&self) -> SingleValueMapper<usize>;
+
+    /// Highest service fee that can be set. 10000 = 100%.
+    /// If not configured, any fee up to 100% can be set.
+    #[storage_mapper("max_service_fee")]
+    fn max_service_fee(&self) -> SingleValueMapper<usize>;
+
+    #[view(getMaxServiceFee)]
+    fn get_max_service_fee(&self) -> usize {
+        if self.max_service_fee().is_empty() {
+            PERCENTAGE_DENOMINATOR
+        } else {
+            self.max_service_fee().get()
+        }
+    }
+
+    /// The bounds can only be tightened, so delegators can rely on them.
+    #[only_owner]
+    #[endpoint(setServiceFeeBounds)]
+    fn set_service_fee_bounds_endpoint(&self, min_service_fee: usize, max_service_fee: usize) {
+        require!(
+            min_service_fee <= max_service_fee && max_service_fee <= PERCENTAGE_DENOMINATOR,
+            "invalid service fee bounds"
+        );
+        require!(
+            min_service_fee >= self.min_service_fee().get()
+                && max_service_fee <= self.get_max_service_fee(),
+            "service fee bounds cannot be widened"
+        );
+        self.min_service_fee().set(min_service_fee);
+        self.max_service_fee().set(max_service_fee);
+    }
+
+    /// How much the service fee can change at once, up or down. 10000 = 100%.
+    /// Zero means no limit.
+    #[view(getServiceFeeMaxChange)]
+    #[storage_mapper("service_fee_max_change")]
+    fn service_fee_max_change(&self) -> SingleValueMapper<usize>;
+
+    /// The maximum change can only be lowered, and once set it cannot be removed.
+    #[only_owner]
+    #[endpoint(setServiceFeeMaxChange)]
+    fn set_service_fee_max_change_endpoint(&self, max_change: usize) {
+        let current_max_change = self.service_fee_max_change().get();
+        require!(
+            current_max_change == 0 || (max_change > 0 && max_change <= current_max_change),
+            "service fee maximum change cannot be increased"
+        );
+        self.service_fee_max_change().set(max_change);
+    }
+
+    /// Checks a new service fee against the bounds and the maximum change configured by the owner.
+    fn validate_service_fee_policy(&self, service_fee_per_10000: usize) {
+        require!(
+            service_fee_per_10000 >= self.min_service_fee().get()
+                && service_fee_per_10000 <= self.get_max_service_fee(),
+            "service fee out of the allowed bounds"
+        );
+
+        let max_change = self.service_fee_max_change().get();
+        if max_change > 0 {
+            let current_service_fee = self.get_service_fee();
+            let new_service_fee = BigUint::from(service_fee_per_10000);
+            let change = if new_service_fee > current_service_fee {
+                new_service_fee - current_service_fee
+            } else {
+                current_service_fee - new_service_fee
+            };
+            require!(
+                change <= max_change as u64,
+                "service fee change exceeds the maximum allowed change"
+            );
+        }
+    }
+
+    /// Minimum number of blocks between announcing a new service fee and being able to apply it.
+    #[view(getServiceFeeNoticeBlocks)]
+    #[storage_mapper("service_fee_notice_blocks")]
+    fn service_fee_notice_blocks(&self) -> SingleValueMapper<u64>;
+
+    /// The notice period can only be increased, so delegators can rely on it.
+    #[only_owner]
+    #[endpoint(setServiceFeeNoticeBlocks)]
+    fn set_service_fee_notice_blocks_endpoint(&self, notice_blocks: u64) {
+        require!(
+            notice_blocks >= self.service_fee_notice_blocks().get(),
+            "service fee notice period cannot be decreased"
+        );
+        self.service_fee_notice_blocks().set(notice_blocks);
+    }
+
+    #[storage_mapper("pending_service_fee")]
+    fn pending_service_fee(&self) -> SingleValueMapper<ServiceFeeAnnouncement>;
+
+    /// The service fee change that was announced and not yet applied, if any.
+    #[view(getPendingServiceFee)]
+    fn get_pending_service_fee(&self) -> OptionalValue<ServiceFeeAnnouncement> {
+        if self.pending_service_fee().is_empty() {
+            OptionalValue::None
+        } else {
+            OptionalValue::Some(self.pending_service_fee().get())
+        }
+    }
+
     #[view(getTotalDelegationCap)]
     #[storage_get("total_delegation_cap")]
     fn get_total_delegation_cap(&self) -> BigUint;
@@ -93,4 +246,222 @@ pub trait SettingsModule {
     fn set_minimum_stake_endpoint(&self, minimum_stake: BigUint) {
         self.set_minimum_stake(&minimum_stake);
     }
+
+    /// Delegators cannot have more than this amount of waiting + active stake.
+    /// Does not apply to the owner. Zero means disabled.
+    #[view(getMaximumUserStake)]
+    #[storage_get("max_user_stake")]
+    fn get_maximum_user_stake(&self) -> BigUint;
+
+    #[storage_set("max_user_stake")]
+    fn set_maximum_user_stake(&self, maximum_user_stake: &BigUint);
+
+    #[only_owner]
+    #[endpoint(setMaximumUserStake)]
+    fn set_maximum_user_stake_endpoint(&self, maximum_user_stake: BigUint) {
+        self.set_maximum_user_stake(&maximum_user_stake);
+    }
+
+    #[view(getWaitingActivationPolicy)]
+    #[storage_mapper("waiting_activation_policy")]
+    fn waiting_activation_policy(&self) -> SingleValueMapper<WaitingActivationPolicy>;
+
+    #[only_owner]
+    #[endpoint(setWaitingActivationPolicy)]
+    fn set_waiting_activation_policy(&self, policy: WaitingActivationPolicy) {
+        self.waiting_activation_policy().set(policy);
+    }
+
+    /// How many entries from the front of the waiting list the largest first and pro rata policies look at in one pass.
+    #[view(getWaitingPolicyWindowSize)]
+    fn get_waiting_policy_window_size(&self) -> usize {
+        WAITING_POLICY_WINDOW_SIZE
+    }
+
+    /// Rewards of delegators that opted in for auto-compounding are only converted into stake
+    /// once they reach this amount, to avoid filling the waiting list with very small entries.
+    /// Zero means any amount gets compounded.
+    #[view(getAutoCompoundMinAmount)]
+    #[storage_get("auto_compound_min_amount")]
+    fn get_auto_compound_min_amount(&self) -> BigUint;
+
+    #[storage_set("auto_compound_min_amount")]
+    fn set_auto_compound_min_amount(&self, auto_compound_min_amount: &BigUint);
+
+    #[only_owner]
+    #[endpoint(setAutoCompoundMinAmount)]
+    fn set_auto_compound_min_amount_endpoint(&self, auto_compound_min_amount: BigUint) {
+        self.set_auto_compound_min_amount(&auto_compound_min_amount);
+    }
+
+    #[storage_mapper("global_op_gas_threshold")]
+    fn global_op_gas_threshold(&self) -> SingleValueMapper<u64>;
+
+    #[view(getGlobalOpGasThreshold)]
+    fn get_global_op_gas_threshold(&self) -> u64 {
+        if self.global_op_gas_threshold().is_empty() {
+            DEFAULT_GLOBAL_OP_GAS_THRESHOLD
+        } else {
+            self.global_op_gas_threshold().get()
+        }
+    }
+
+    #[only_owner]
+    #[endpoint(setGlobalOpGasThreshold)]
+    fn set_global_op_gas_threshold_endpoint(&self, gas_threshold: u64) {
+        self.validate_gas_threshold(gas_threshold);
+        self.global_op_gas_threshold().set(gas_threshold);
+    }
+
+    #[storage_mapper("dust_cleanup_gas_threshold")]
+    fn dust_cleanup_gas_threshold(&self) -> SingleValueMapper<u64>;
+
+    #[view(getDustCleanupGasThreshold)]
+    fn get_dust_cleanup_gas_threshold(&self) -> u64 {
+        if self.dust_cleanup_gas_threshold().is_empty() {
+            DEFAULT_DUST_CLEANUP_GAS_THRESHOLD
+        } else {
+            self.dust_cleanup_gas_threshold().get()
+        }
+    }
+
+    #[only_owner]
+    #[endpoint(setDustCleanupGasThreshold)]
+    fn set_dust_cleanup_gas_threshold_endpoint(&self, gas_threshold: u64) {
+        self.validate_gas_threshold(gas_threshold);
+        self.dust_cleanup_gas_threshold().set(gas_threshold);
+    }
+
+    #[storage_mapper("unbond_gas_threshold")]
+    fn unbond_gas_threshold(&self) -> SingleValueMapper<u64>;
+
+    #[view(getUnBondGasThreshold)]
+    fn get_unbond_gas_threshold(&self) -> u64 {
+        if self.unbond_gas_threshold().is_empty() {
+            DEFAULT_UNBOND_GAS_THRESHOLD
+        } else {
+            self.unbond_gas_threshold().get()
+        }
+    }
+
+    #[only_owner]
+    #[endpoint(setUnBondGasThreshold)]
+    fn set_unbond_gas_threshold_endpoint(&self, gas_threshold: u64) {
+        self.validate_gas_threshold(gas_threshold);
+        self.unbond_gas_threshold().set(gas_threshold);
+    }
+
+    #[storage_mapper("auto_compound_gas_threshold")]
+    fn auto_compound_gas_threshold(&self) -> SingleValueMapper<u64>;
+
+    #[view(getAutoCompoundGasThreshold)]
+    fn get_auto_compound_gas_threshold(&self) -> u64 {
+        if self.auto_compound_gas_threshold().is_empty() {
+            DEFAULT_AUTO_COMPOUND_GAS_THRESHOLD
+        } else {
+            self.auto_compound_gas_threshold().get()
+        }
+    }
+
+    #[only_owner]
+    #[endpoint(setAutoCompoundGasThreshold)]
+    fn set_auto_compound_gas_threshold_endpoint(&self, gas_threshold: u64) {
+        self.validate_gas_threshold(gas_threshold);
+        self.auto_compound_gas_threshold().set(gas_threshold);
+    }
+
+    fn validate_gas_threshold(&self, gas_threshold: u64) {
+        require!(
+            gas_threshold >= MIN_GAS_THRESHOLD,
+            "gas threshold below the minimum"
+        );
+    }
+
+    /// Interruptible endpoints can be given a gas threshold for that call only,
+    /// otherwise the configured one applies.
+    fn resolve_gas_threshold(&self, configured: u64, opt_gas_threshold: OptionalValue<u64>) -> u64 {
+        match opt_gas_threshold {
+            OptionalValue::Some(gas_threshold) => {
+                self.validate_gas_threshold(gas_threshold);
+                gas_threshold
+            }
+            OptionalValue::None => configured,
+        }
+    }
+
+    /// Funds set aside to pay keepers for driving long-running operations forward.
+    /// Not part of the rewards or of the stake of any delegator.
+    /// Check `getKeeperPool` for the up to date value.
+    #[storage_mapper("keeper_pool")]
+    fn keeper_pool(&self) -> SingleValueMapper<BigUint>;
+
+    /// The part of the service fee that goes to the keeper pool instead of the owner. 10000 = 100%.
+    #[view(getKeeperServiceFeeShare)]
+    #[storage_mapper("keeper_fee_share")]
+    fn keeper_service_fee_share(&self) -> SingleValueMapper<usize>;
+
+    /// When enabled, only addresses approved by the owner or by the allow list manager can stake.
+    /// Removing an address from the list does not affect its existing stake,
+    /// it can still be unstaked and unbonded.
+    #[view(isAllowListEnabled)]
+    #[storage_mapper("allow_list_enabled")]
+    fn allow_list_enabled(&self) -> SingleValueMapper<bool>;
+
+    #[only_owner]
+    #[endpoint(setAllowListEnabled)]
+    fn set_allow_list_enabled_endpoint(&self, enabled: bool) {
+        self.allow_list_enabled().set(enabled);
+    }
+
+    /// Address that, besides the owner, can add and remove addresses from the allow list.
+    #[view(getAllowListManager)]
+    #[storage_mapper("allow_list_manager")]
+    fn allow_list_manager(&self) -> SingleValueMapper<ManagedAddress>;
+
+    #[only_owner]
+    #[endpoint(setAllowListManager)]
+    fn set_allow_list_manager_endpoint(&self, manager: ManagedAddress) {
+        self.allow_list_manager().set(&manager);
+    }
+
+    #[view(isAllowListed)]
+    #[storage_mapper("allow_list")]
+    fn allow_list(&self, address: &ManagedAddress) -> SingleValueMapper<bool>;
+
+    #[endpoint(addToAllowList)]
+    fn add_to_allow_list(&self, addresses: MultiValueEncoded<ManagedAddress>) {
+        self.require_owner_or_allow_list_manager();
+        for address in addresses.into_iter() {
+            self.allow_list(&address).set(true);
+        }
+    }
+
+    #[endpoint(removeFromAllowList)]
+    fn remove_from_allow_list(&self, addresses: MultiValueEncoded<ManagedAddress>) {
+        self.require_owner_or_allow_list_manager();
+        for address in addresses.into_iter() {
+            self.allow_list(&address).clear();
+        }
+    }
+
+    fn require_owner_or_allow_list_manager(&self) {
+        let caller = self.blockchain().get_caller();
+        require!(
+            caller == self.blockchain().get_owner_address()
+                || (!self.allow_list_manager().is_empty()
+                    && caller == self.allow_list_manager().get()),
+            "only owner or allow list manager can update the allow list"
+        );
+    }
+
+    /// Yields true if the address can receive new stake, either because it is on the allow list,
+    /// or because the allow list is disabled.
+    /// The owner can always stake, otherwise the minimum owner stake share could become impossible to keep.
+    #[view(canStake)]
+    fn can_stake(&self, address: &ManagedAddress) -> bool {
+        !self.allow_list_enabled().get()
+            || self.allow_list(address).get()
+            || self.get_user_id(address) == OWNER_USER_ID.get()
+            || address == &self.blockchain().get_owner_address()
+    }
 }
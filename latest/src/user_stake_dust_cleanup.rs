@@ -1,19 +1,23 @@
+use crate::roles::Role;
 use crate::settings::OWNER_USER_ID;
 use core::num::NonZeroUsize;
+use user_fund_storage::fund_view_module::USER_STAKE_TOTALS_ID;
 use user_fund_storage::types::{FundDescription, FundType};
 
 multiversx_sc::imports!();
 
-pub const DUST_GASLIMIT: u64 = 20_000_000;
-
 /// Functionality for cleaning up very small amounts left in the waiting list.
 #[multiversx_sc::derive::module]
 pub trait UserStakeDustCleanupModule:
     crate::user_stake_state::UserStakeStateModule
+    + crate::liquid_staking_state::LiquidStakingStateModule
     + crate::reset_checkpoint_state::ResetCheckpointStateModule
     + crate::rewards_state::RewardStateModule
     + crate::settings::SettingsModule
     + crate::events::EventsModule
+    + crate::keeper_bounty::KeeperBountyModule
+    + crate::roles::RolesModule
+    + multiversx_sc_modules::pause::PauseModule
     + user_fund_storage::user_data::UserDataModule
     + user_fund_storage::fund_module::FundModule
     + user_fund_storage::fund_view_module::FundViewModule
@@ -24,6 +28,19 @@ pub trait UserStakeDustCleanupModule:
     #[storage_mapper("dust_cleanup_checkpoint")]
     fn dust_cleanup_checkpoint(&self) -> SingleValueMapper<usize>;
 
+    /// Node operators can pick any dust limit, keepers only up to the one set by the owner.
+    fn require_dust_cleanup_caller(&self, dust_limit: &BigUint) {
+        self.require_any_role(&[Role::NodeOperator, Role::Keeper]);
+
+        let caller = self.blockchain().get_caller();
+        if !self.has_role(Role::NodeOperator, &caller) {
+            require!(
+                *dust_limit <= self.keeper_dust_limit().get(),
+                "dust limit above the keeper dust limit"
+            );
+        }
+    }
+
     /// Counts fund buckets in the waiting list that are below a certain threshold.
     /// Unlike most views, yields the number of entries, rather than the sum of EGLD.
     #[view(countDustItemsWaitingList)]
@@ -47,23 +64,39 @@ pub trait UserStakeDustCleanupModule:
     /// Does not block the rest of the contract. If any operation interferes with an interrupted
     /// dust cleanup, the operation can be begun again.
     /// It will auto-reset if the list ends or the current item is no longer valid.
-    #[only_owner]
+    /// Pays the keeper bounty if any stake was cleaned up.
+    /// Optionally, the gas threshold at which to stop can be overridden for this call.
     #[endpoint(dustCleanupWaitingList)]
-    fn dust_cleanup_waiting_list(&self, dust_limit: &BigUint) {
+    fn dust_cleanup_waiting_list(
+        &self,
+        dust_limit: &BigUint,
+        opt_gas_threshold: OptionalValue<u64>,
+    ) {
+        self.require_dust_cleanup_caller(dust_limit);
+        let gas_threshold =
+            self.resolve_gas_threshold(self.get_dust_cleanup_gas_threshold(), opt_gas_threshold);
+
         require!(
             !self.is_global_op_in_progress(),
             "contract is temporarily paused as checkpoint is reset"
         );
 
+        let total_waiting_before =
+            self.get_user_stake_of_type(USER_STAKE_TOTALS_ID, FundType::Waiting);
         self.dust_cleanup_checkpoint().update(|checkpoint| {
             self.swap_dust(
                 checkpoint,
                 dust_limit,
                 FundType::Waiting,
                 |_| Some(FundDescription::WithdrawOnly),
-                || self.blockchain().get_gas_left() < DUST_GASLIMIT,
+                || self.blockchain().get_gas_left() < gas_threshold,
             );
         });
+        if self.get_user_stake_of_type(USER_STAKE_TOTALS_ID, FundType::Waiting)
+            < total_waiting_before
+        {
+            self.pay_keeper_bounty(b"dustCleanupWaitingList", b"");
+        }
     }
 
     /// Unstakes and unbonds all active fund buckets that are below a certin threshold.
@@ -72,28 +105,42 @@ pub trait UserStakeDustCleanupModule:
     /// Does not block the rest of the contract. If any operation interferes with an interrupted
     /// dust cleanup, the operation can be begun again.
     /// It will auto-reset if the list ends or the current item is no longer valid.
-    #[only_owner]
+    /// Pays the keeper bounty if any stake was cleaned up.
+    /// Optionally, the gas threshold at which to stop can be overridden for this call.
     #[endpoint(dustCleanupActive)]
-    fn dust_cleanup_active(&self, dust_limit: &BigUint) {
+    fn dust_cleanup_active(&self, dust_limit: &BigUint, opt_gas_threshold: OptionalValue<u64>) {
+        self.require_dust_cleanup_caller(dust_limit);
+        let gas_threshold =
+            self.resolve_gas_threshold(self.get_dust_cleanup_gas_threshold(), opt_gas_threshold);
+
         require!(
             !self.is_global_op_in_progress(),
             "contract is temporarily paused as checkpoint is reset"
         );
 
         // reserve half of the gas for the subsequent swap Waiting -> Active
-        let reserved_gas = self.blockchain().get_gas_left() / 2 + DUST_GASLIMIT;
+        let reserved_gas = self.blockchain().get_gas_left() / 2 + gas_threshold;
 
         // rewards need to be computed for
-        self.compute_one_user_reward(OWNER_USER_ID);
+        self.settle_one_user_reward(OWNER_USER_ID);
+
+        // the liquid staking pool is the contract itself, nobody could unbond its stake
+        let pool_user_id = self.get_liquid_pool_user_id();
 
+        let total_active_before =
+            self.get_user_stake_of_type(USER_STAKE_TOTALS_ID, FundType::Active);
         self.dust_cleanup_checkpoint().update(|checkpoint| {
             self.swap_dust(
                 checkpoint,
                 dust_limit,
                 FundType::Active,
                 |fund_item| {
+                    if fund_item.user_id == pool_user_id {
+                        return None;
+                    }
                     if let Some(user_id_nz) = NonZeroUsize::new(fund_item.user_id) {
-                        self.compute_one_user_reward(user_id_nz);
+                        // compounding would create new dust in the waiting list
+                        self.settle_one_user_reward(user_id_nz);
                         Some(FundDescription::UnStaked { created: 0 })
                     } else {
                         None
@@ -103,6 +150,11 @@ pub trait UserStakeDustCleanupModule:
             );
         });
 
+        if self.get_user_stake_of_type(USER_STAKE_TOTALS_ID, FundType::Active) < total_active_before
+        {
+            self.pay_keeper_bounty(b"dustCleanupActive", b"");
+        }
+
         // move funds around
         self.use_waiting_to_replace_unstaked();
     }
@@ -4,13 +4,12 @@ use user_fund_storage::types::FundType;
 
 multiversx_sc::imports!();
 
-pub const UNBOND_GASLIMIT: u64 = 50_000_000;
-
 #[multiversx_sc::derive::module]
 pub trait UserStakeEndpointsModule:
     crate::user_stake_state::UserStakeStateModule
     + crate::reset_checkpoint_state::ResetCheckpointStateModule
     + crate::rewards_state::RewardStateModule
+    + crate::liquid_staking_state::LiquidStakingStateModule
     + crate::settings::SettingsModule
     + crate::events::EventsModule
     + user_fund_storage::user_data::UserDataModule
@@ -42,6 +41,9 @@ pub trait UserStakeEndpointsModule:
 
     /// unStake - the user will announce that he wants to get out of the contract
     /// selected funds will change from active to inactive, but claimable only after unBond period ends
+    /// Liquid staking tokens can be sent along: they are burned and the matching active stake
+    /// is unstaked on top of the amount given. Only the amount given is taken out of the waiting list first.
+    #[payable("*")]
     #[endpoint(unStake)]
     fn unstake_endpoint(&self, amount: BigUint) {
         require!(self.not_paused(), "contract paused");
@@ -52,23 +54,37 @@ pub trait UserStakeEndpointsModule:
         );
 
         let caller = self.blockchain().get_caller();
+        let (token_id, token_amount) = self.call_value().egld_or_single_fungible_esdt();
+        let mut redeemed = BigUint::zero();
+        if token_id.is_esdt() || token_amount > 0 {
+            self.require_liquid_token_payment(&token_id);
+            // no allow list check, the redeemed stake leaves right away
+            let user_id = NonZeroUsize::new(self.get_or_create_user(&caller))
+                .unwrap_or_else(|| sc_panic!("bad user_id"));
+            redeemed = self.redeem_liquid_tokens(user_id, &token_amount);
+        }
+
         let unstake_user_id = NonZeroUsize::new(self.get_user_id(&caller))
             .unwrap_or_else(|| sc_panic!("only delegators can unstake"));
 
         // validate that amount does not exceed existing waiting + active stake
-        self.validate_unstake_amount(unstake_user_id.get(), &amount);
+        self.validate_unstake_amount(unstake_user_id.get(), &(&amount + &redeemed));
 
         // first try to remove funds from waiting list
         let mut remaining = amount;
         self.swap_user_waiting_to_withdraw_only(unstake_user_id.get(), &mut remaining);
+
+        // the redeemed stake is active, it never comes out of the waiting list
+        remaining += redeemed;
         if remaining == 0 {
             // waiting list entries covered the whole sum
             return;
         }
 
         // compute rewards before converting Active -> UnStaked
-        self.compute_one_user_reward(OWNER_USER_ID);
-        self.compute_one_user_reward(unstake_user_id);
+        // no compounding here, it would put stake back in for a user who is leaving
+        self.settle_one_user_reward(OWNER_USER_ID);
+        self.settle_one_user_reward(unstake_user_id);
 
         // convert Active -> UnStaked
         self.swap_user_active_to_unstaked(unstake_user_id.get(), &mut remaining);
@@ -81,6 +97,131 @@ pub trait UserStakeEndpointsModule:
         self.validate_user_minimum_stake(unstake_user_id.get());
     }
 
+    /// Takes stake out of the waiting list and pays it out right away,
+    /// same as `unStake` followed by `unBond`, when only waiting stake is unstaked.
+    /// Any other stake that was already withdrawable is paid out as well.
+    /// Returns the total amount sent.
+    /// Optionally, the gas threshold at which to stop paying out can be overridden for this call.
+    #[endpoint(withdrawWaiting)]
+    fn withdraw_waiting_endpoint(
+        &self,
+        amount: BigUint,
+        opt_gas_threshold: OptionalValue<u64>,
+    ) -> BigUint {
+        require!(self.not_paused(), "contract paused");
+        let gas_threshold =
+            self.resolve_gas_threshold(self.get_unbond_gas_threshold(), opt_gas_threshold);
+
+        require!(
+            !self.is_global_op_in_progress(),
+            "withdrawing is temporarily paused as checkpoint is reset"
+        );
+
+        let caller = self.blockchain().get_caller();
+        let user_id = self.get_user_id(&caller);
+        require!(user_id > 0, "only delegators can withdraw");
+
+        require!(amount > 0, "cannot withdraw zero stake");
+        require!(
+            amount <= self.get_user_stake_of_type(user_id, FundType::Waiting),
+            "cannot withdraw more than the user waiting stake"
+        );
+        self.validate_unstake_amount(user_id, &amount);
+
+        let mut remaining = amount;
+        self.swap_user_waiting_to_withdraw_only(user_id, &mut remaining);
+        require!(remaining == 0, "error converting Waiting to WithdrawOnly");
+
+        self.validate_user_minimum_stake(user_id);
+
+        let amount_liquidated = self.liquidate_all_withdraw_only(user_id, || {
+            self.blockchain().get_gas_left() < gas_threshold
+        });
+
+        // the payout can stop early when running low on gas, so only what was sent gets logged
+        self.withdraw_waiting_event(&caller, &amount_liquidated);
+        if amount_liquidated > 0 {
+            self.send().direct_egld(&caller, &amount_liquidated);
+        }
+
+        amount_liquidated
+    }
+
+    /// Transfers active stake to another address, which becomes a delegator if it wasn't already.
+    /// The stake does not go through the unstake/unbond cycle, it stays active the whole time.
+    #[endpoint(transferActiveStake)]
+    fn transfer_active_stake_endpoint(&self, to: ManagedAddress, amount: BigUint) {
+        require!(self.not_paused(), "contract paused");
+        self.check_feature_on(b"transferActiveStake", true);
+
+        require!(
+            !self.is_global_op_in_progress(),
+            "transferring stake is temporarily paused as checkpoint is reset"
+        );
+
+        let caller = self.blockchain().get_caller();
+        let from_user_id = NonZeroUsize::new(self.get_user_id(&caller))
+            .unwrap_or_else(|| sc_panic!("only delegators can transfer stake"));
+
+        require!(caller != to, "cannot transfer stake to self");
+        require!(
+            to != self.blockchain().get_sc_address(),
+            "cannot transfer stake to the contract itself"
+        );
+        require!(self.can_stake(&to), "recipient not allowed to stake");
+        require!(amount > 0, "cannot transfer zero stake");
+        require!(
+            amount <= self.get_user_stake_of_type(from_user_id.get(), FundType::Active),
+            "cannot transfer more than the user active stake"
+        );
+
+        let to_user_id = NonZeroUsize::new(self.get_or_create_user(&to))
+            .unwrap_or_else(|| sc_panic!("bad user_id"));
+
+        // compute rewards before the active stake changes hands, without compounding,
+        // so that the sender can transfer the entire active stake
+        self.settle_one_user_reward(from_user_id);
+        self.settle_one_user_reward(to_user_id);
+
+        self.transfer_active_stake_event(&caller, &to, &amount);
+
+        let mut remaining = amount;
+        self.transfer_user_active(from_user_id.get(), to_user_id.get(), &mut remaining);
+        require!(remaining == 0, "error transferring active stake");
+
+        // check that minimum stake was not violated
+        self.validate_user_minimum_stake(from_user_id.get());
+        self.validate_user_minimum_stake(to_user_id.get());
+        self.validate_user_maximum_stake(to_user_id.get());
+    }
+
+    /// Delegators can opt in to have their rewards automatically converted into new stake,
+    /// instead of having to claim them.
+    #[endpoint(setAutoCompound)]
+    fn set_auto_compound_endpoint(&self, enabled: bool) {
+        require!(self.not_paused(), "contract paused");
+
+        require!(
+            !self.is_global_op_in_progress(),
+            "contract is temporarily paused as checkpoint is reset"
+        );
+
+        let caller = self.blockchain().get_caller();
+        let user_id = NonZeroUsize::new(self.get_user_id(&caller))
+            .unwrap_or_else(|| sc_panic!("unknown caller"));
+
+        // rewards accumulated so far are computed according to the previous preference
+        self.compute_one_user_reward(user_id);
+
+        self.user_auto_compound(user_id.get()).set(enabled);
+    }
+
+    #[view(isAutoCompoundEnabled)]
+    fn is_auto_compound_enabled(&self, user_address: ManagedAddress) -> bool {
+        let user_id = self.get_user_id(&user_address);
+        user_id > 0 && self.user_auto_compound(user_id).get()
+    }
+
     #[view(getUnStakeable)]
     fn get_unstakeable(&self, user_address: ManagedAddress) -> BigUint {
         let user_id = self.get_user_id(&user_address);
@@ -92,9 +233,12 @@ pub trait UserStakeEndpointsModule:
         }
     }
 
+    /// Optionally, the gas threshold at which to stop paying out can be overridden for this call.
     #[endpoint(unBond)]
-    fn unbond_user(&self) -> BigUint {
+    fn unbond_user(&self, opt_gas_threshold: OptionalValue<u64>) -> BigUint {
         require!(self.not_paused(), "contract paused");
+        let gas_threshold =
+            self.resolve_gas_threshold(self.get_unbond_gas_threshold(), opt_gas_threshold);
 
         let caller = self.blockchain().get_caller();
         let caller_id = self.get_user_id(&caller);
@@ -102,14 +246,16 @@ pub trait UserStakeEndpointsModule:
 
         let n_blocks_before_unbond = self.get_n_blocks_before_unbond();
         let _ = self.swap_eligible_deferred_to_withdraw(caller_id, n_blocks_before_unbond, || {
-            self.blockchain().get_gas_left() < UNBOND_GASLIMIT
+            self.blockchain().get_gas_left() < gas_threshold
         });
 
         let amount_liquidated = self.liquidate_all_withdraw_only(caller_id, || {
-            self.blockchain().get_gas_left() < UNBOND_GASLIMIT
+            self.blockchain().get_gas_left() < gas_threshold
         });
 
         if amount_liquidated > 0 {
+            self.unbond_event(&caller, &amount_liquidated);
+
             // forward payment to seller
             self.send().direct_egld(&caller, &amount_liquidated);
         }
@@ -1,6 +1,7 @@
 use core::cmp::Ordering;
 use core::num::NonZeroUsize;
 
+use crate::settings::WaitingActivationPolicy;
 use crate::settings::OWNER_USER_ID;
 use crate::settings::PERCENTAGE_DENOMINATOR;
 use user_fund_storage::fund_view_module::USER_STAKE_TOTALS_ID;
@@ -17,6 +18,7 @@ pub trait UserStakeStateModule:
     + user_fund_storage::fund_view_module::FundViewModule
     + user_fund_storage::fund_transf_module::FundTransformationsModule
     + crate::rewards_state::RewardStateModule
+    + crate::liquid_staking_state::LiquidStakingStateModule
     + crate::events::EventsModule
 {
     fn process_stake(&self, payment: BigUint) {
@@ -24,6 +26,7 @@ pub trait UserStakeStateModule:
         // we use user id as an intermediate identifier between user address and data,
         // because we might at some point need to iterate over all user data
         let caller = self.blockchain().get_caller();
+        require!(self.can_stake(&caller), "caller not allowed to stake");
         let user_id = self.get_or_create_user(&caller);
 
         // log staking event
@@ -31,6 +34,7 @@ pub trait UserStakeStateModule:
 
         // create stake funds
         self.create_waiting(user_id, payment);
+        self.validate_user_maximum_stake(user_id);
 
         // check invariant
         self.validate_delegation_cap_invariant();
@@ -67,7 +71,7 @@ pub trait UserStakeStateModule:
 
             // swap waiting -> active, but no more than fillable
             // no need to worry about rewards here, because there aren't any
-            let _ = self.swap_waiting_to_active(&mut fillable_active_stake, || false);
+            self.swap_waiting_to_active_by_policy(&mut fillable_active_stake, || false);
             if fillable_active_stake == 0 {
                 // this happens only when waiting was enough to fill the delegation cap
                 self.set_bootstrap_mode(false);
@@ -93,31 +97,119 @@ pub trait UserStakeStateModule:
         }
     }
 
-    /// Swaps waiting stake to active within given limits,
-    /// and also computes rewards for all affected users before performing the swap itself.
+    /// Swaps waiting stake to active within given limits.
+    /// Rewards are computed for each affected user right before their stake is activated.
     fn swap_waiting_to_active_compute_rewards(&self, swappable: &BigUint) {
-        // dry run of swap, to get the affected users
-        let (affected_users, remaining) =
-            self.get_affected_users_of_swap_waiting_to_active(swappable, || false);
+        self.compute_one_user_reward(OWNER_USER_ID);
+
+        let mut remaining = swappable.clone();
+        self.swap_waiting_to_active_by_policy(&mut remaining, || false);
         require!(remaining == 0u32, "error swapping waiting to active");
+    }
+
+    /// Interruptible version of `swap_waiting_to_active_compute_rewards`, for global operations.
+    /// Only the users reached before the interruption get their rewards computed.
+    fn compute_rewards_and_swap_waiting_to_active<I: Fn() -> bool>(
+        &self,
+        remaining: &mut BigUint,
+        interrupt: I,
+    ) {
+        if *remaining == 0 {
+            return;
+        }
+        self.compute_one_user_reward(OWNER_USER_ID);
+        self.swap_waiting_to_active_by_policy(remaining, interrupt);
+    }
 
-        // compute rewards for all affected users
+    /// Computes rewards for each user right before their active stake is swapped, for global operations.
+    /// The liquid staking pool is skipped: its stake could never be unbonded,
+    /// since the pool user is the contract itself.
+    fn compute_rewards_and_swap_active_to_deferred_payment<I: Fn() -> bool>(
+        &self,
+        remaining: &mut BigUint,
+        interrupt: I,
+    ) {
+        if *remaining == 0 {
+            return;
+        }
         self.compute_one_user_reward(OWNER_USER_ID);
-        for user_id in affected_users.into_iter() {
+        let pool_user_id = self.get_liquid_pool_user_id();
+        let mut last_user_id = 0usize;
+        self.swap_active_to_deferred_payment_with_hook(
+            remaining,
+            |user_id| {
+                if user_id == pool_user_id {
+                    return false;
+                }
+                self.settle_rewards_before_swap(user_id, &mut last_user_id);
+                true
+            },
+            interrupt,
+        );
+    }
+
+    /// Used by swaps, brings the rewards of a user up to date right before their stake moves.
+    /// Consecutive entries often belong to the same user, these are only settled once.
+    fn settle_rewards_before_swap(&self, user_id: usize, last_user_id: &mut usize) {
+        if user_id != *last_user_id {
+            *last_user_id = user_id;
             let user_id_nz = NonZeroUsize::new(user_id).unwrap_or_else(|| sc_panic!("bad user_id"));
-            self.compute_one_user_reward(user_id_nz);
+            self.settle_one_user_reward(user_id_nz);
         }
+    }
 
-        // actual swap of waiting to active
-        let mut remaining = swappable.clone();
-        let _ = self.swap_waiting_to_active(&mut remaining, || false);
-        require!(remaining == 0u32, "error swapping waiting to active");
+    /// Swaps waiting stake to active, in the order given by the waiting activation policy.
+    /// The rewards of each user are brought up to date right before their stake is activated,
+    /// so only the users actually reached are visited.
+    /// If liquid staking is active, the stake activated for eligible users is recorded as pending tokenization,
+    /// one user at a time, as the swap moves on to the next user.
+    fn swap_waiting_to_active_by_policy<I: Fn() -> bool>(
+        &self,
+        remaining: &mut BigUint,
+        interrupt: I,
+    ) {
+        let tokenize = self.is_liquid_staking_active();
+        // the eligible user currently being swapped, with their active stake right before
+        let mut tokenized_user: Option<(usize, BigUint)> = None;
+        let mut last_user_id = 0usize;
+        let before_swap = |user_id| {
+            if user_id != last_user_id {
+                if let Some((prev_user_id, active_before)) = tokenized_user.take() {
+                    self.add_pending_liquid_stake(prev_user_id, &active_before);
+                }
+                if tokenize && self.is_liquid_staking_eligible(user_id) {
+                    let active_before = self.get_user_stake_of_type(user_id, FundType::Active);
+                    tokenized_user = Some((user_id, active_before));
+                }
+            }
+            self.settle_rewards_before_swap(user_id, &mut last_user_id)
+        };
+        match self.waiting_activation_policy().get() {
+            WaitingActivationPolicy::FirstInFirstOut => {
+                let _ = self.swap_waiting_to_active_with_hook(remaining, before_swap, interrupt);
+            }
+            WaitingActivationPolicy::LargestFirst => {
+                self.swap_waiting_to_active_largest_first(remaining, before_swap, interrupt);
+            }
+            WaitingActivationPolicy::ProRata => {
+                self.swap_waiting_to_active_pro_rata(remaining, before_swap, interrupt);
+            }
+            WaitingActivationPolicy::OwnerFirst => {
+                self.settle_one_user_reward(OWNER_USER_ID);
+                self.swap_user_waiting_to_active(OWNER_USER_ID.get(), remaining);
+                let _ = self.swap_waiting_to_active_with_hook(remaining, before_swap, interrupt);
+            }
+        }
+
+        if let Some((user_id, active_before)) = tokenized_user {
+            self.add_pending_liquid_stake(user_id, &active_before);
+        }
     }
 
     /// Mostly invariant: modifyTotalDelegationCap can violate this rule.
     fn validate_user_minimum_stake(&self, user_id: usize) {
         let waiting = self.get_user_stake_of_type(user_id, FundType::Waiting);
-        let active = self.get_user_stake_of_type(USER_STAKE_TOTALS_ID, FundType::Active);
+        let active = self.get_user_stake_of_type(user_id, FundType::Active);
         let relevant_stake = &waiting + &active;
 
         require!(
@@ -126,6 +218,45 @@ pub trait UserStakeStateModule:
         );
     }
 
+    /// Not enforced retroactively: lowering the maximum does not affect existing stake,
+    /// but prevents those users from adding more.
+    fn validate_user_maximum_stake(&self, user_id: usize) {
+        let maximum_stake = self.get_maximum_user_stake();
+        if maximum_stake == 0 || user_id == OWNER_USER_ID.get() {
+            return;
+        }
+
+        let waiting = self.get_user_stake_of_type(user_id, FundType::Waiting);
+        let active = self.get_user_stake_of_type(user_id, FundType::Active);
+        require!(
+            waiting + active <= maximum_stake,
+            "cannot have waiting + active stake more than maximum stake"
+        );
+    }
+
+    /// How much waiting + active stake the user can still add before reaching the maximum user stake.
+    /// Yields nothing if the user is not limited.
+    #[view(getUserStakeHeadroom)]
+    fn get_user_stake_headroom(&self, user_address: ManagedAddress) -> OptionalValue<BigUint> {
+        let maximum_stake = self.get_maximum_user_stake();
+        let user_id = self.get_user_id(&user_address);
+        if maximum_stake == 0 || user_id == OWNER_USER_ID.get() {
+            return OptionalValue::None;
+        }
+
+        let mut relevant_stake = BigUint::zero();
+        if user_id > 0 {
+            relevant_stake += self.get_user_stake_of_type(user_id, FundType::Waiting);
+            relevant_stake += self.get_user_stake_of_type(user_id, FundType::Active);
+        }
+
+        if relevant_stake >= maximum_stake {
+            OptionalValue::Some(BigUint::zero())
+        } else {
+            OptionalValue::Some(maximum_stake - relevant_stake)
+        }
+    }
+
     /// Invariant: should never return error.
     #[view(validateOwnerStakeShare)]
     fn validate_owner_stake_share(&self) {
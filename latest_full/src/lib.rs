@@ -1,7 +1,9 @@
 #![no_std]
 #![allow(clippy::string_lit_as_bytes)]
 
-use delegation_latest::settings::{OWNER_USER_ID, PERCENTAGE_DENOMINATOR};
+use delegation_latest::settings::{
+    DEFAULT_SERVICE_FEE_NOTICE_BLOCKS, OWNER_USER_ID, PERCENTAGE_DENOMINATOR,
+};
 
 multiversx_sc::imports!();
 
@@ -18,10 +20,16 @@ pub trait DelegationFull:
     + delegation_latest::rewards_state::RewardStateModule
     + delegation_latest::user_stake_state::UserStakeStateModule
     + delegation_latest::events::EventsModule
+    + delegation_latest::keeper_bounty::KeeperBountyModule
     + delegation_latest::reset_checkpoint_endpoints::ResetCheckpointsModule
     + delegation_latest::rewards_endpoints::RewardEndpointsModule
     + delegation_latest::user_stake_endpoints::UserStakeEndpointsModule
     + delegation_latest::user_stake_dust_cleanup::UserStakeDustCleanupModule
+    + delegation_latest::liquid_staking_state::LiquidStakingStateModule
+    + delegation_latest::liquid_staking_endpoints::LiquidStakingEndpointsModule
+    + delegation_latest::roles::RolesModule
+    + delegation_latest::delegator_info::DelegatorInfoModule
+    + delegation_latest::contract_health::ContractHealthModule
     + delegation_latest::multiversx_sc_modules::dns::DnsModule
     + delegation_latest::multiversx_sc_modules::features::FeaturesModule
     + delegation_latest::multiversx_sc_modules::pause::PauseModule
@@ -36,6 +44,8 @@ pub trait DelegationFull:
     // INIT
 
     /// This is the contract constructor, called only once when the contract is deployed.
+    /// The owner user (#1, the node reward destination) is the deployer, unless another address is given.
+    /// The latter is used when the contract is deployed by another contract, such as the delegation manager.
     #[init]
     fn init(
         &self,
@@ -45,8 +55,12 @@ pub trait DelegationFull:
         n_blocks_before_unbond: u64,
         minimum_stake: BigUint,
         total_delegation_cap: BigUint,
+        opt_owner_user_address: OptionalValue<ManagedAddress>,
     ) {
-        let owner = self.blockchain().get_caller();
+        let owner = match opt_owner_user_address {
+            OptionalValue::Some(owner_user_address) => owner_user_address,
+            OptionalValue::None => self.blockchain().get_caller(),
+        };
         self.set_user_id(&owner, OWNER_USER_ID.get()); // node reward destination will be user #1
         self.set_user_address(OWNER_USER_ID.get(), &owner);
         self.set_num_users(1);
@@ -60,6 +74,8 @@ pub trait DelegationFull:
 
         let next_service_fee = BigUint::from(service_fee_per_10000);
         self.set_service_fee(next_service_fee);
+        self.service_fee_notice_blocks()
+            .set(DEFAULT_SERVICE_FEE_NOTICE_BLOCKS);
 
         self.set_owner_min_stake_share_validated(owner_min_stake_share_per_10000);
 
@@ -68,5 +84,7 @@ pub trait DelegationFull:
 
         self.set_total_delegation_cap(total_delegation_cap);
         self.set_bootstrap_mode(true);
+
+        self.init_reward_index();
     }
 }
@@ -3,6 +3,26 @@ fn activate_nodes_go() {
     multiversx_sc_scenario::run_go("scenarios/activate_nodes.scen.json");
 }
 
+#[test]
+fn abort_global_operation_go() {
+    multiversx_sc_scenario::run_go("scenarios/abort_global_operation.scen.json");
+}
+
+#[test]
+fn allow_list_go() {
+    multiversx_sc_scenario::run_go("scenarios/allow_list.scen.json");
+}
+
+#[test]
+fn auto_compound_go() {
+    multiversx_sc_scenario::run_go("scenarios/auto_compound.scen.json");
+}
+
+#[test]
+fn auto_compound_unstake_go() {
+    multiversx_sc_scenario::run_go("scenarios/auto_compound_unstake.scen.json");
+}
+
 #[test]
 fn change_service_fee_go() {
     multiversx_sc_scenario::run_go("scenarios/change_service_fee.scen.json");
@@ -18,6 +38,11 @@ fn claim_rewards_owner_with_stake_go() {
     multiversx_sc_scenario::run_go("scenarios/claim_rewards_owner_with_stake.scen.json");
 }
 
+#[test]
+fn claim_rewards_to_go() {
+    multiversx_sc_scenario::run_go("scenarios/claim_rewards_to.scen.json");
+}
+
 #[test]
 fn claim_rewards_with_changed_service_fee_go() {
     multiversx_sc_scenario::run_go("scenarios/claim_rewards_with_changed_service_fee.scen.json");
@@ -38,21 +63,111 @@ fn continue_global_operations_go() {
     multiversx_sc_scenario::run_go("scenarios/continue_global_operations.scen.json");
 }
 
+#[test]
+fn contract_health_go() {
+    multiversx_sc_scenario::run_go("scenarios/contract_health.scen.json");
+}
+
 #[test]
 fn decrease_cap_in_bootstrap_mode_go() {
     multiversx_sc_scenario::run_go("scenarios/decrease_cap_in_bootstrap_mode.scen.json");
 }
 
+#[test]
+fn delegator_info_go() {
+    multiversx_sc_scenario::run_go("scenarios/delegator_info.scen.json");
+}
+
+#[test]
+fn fund_movement_events_go() {
+    multiversx_sc_scenario::run_go("scenarios/fund_movement_events.scen.json");
+}
+
+#[test]
+fn gas_thresholds_go() {
+    multiversx_sc_scenario::run_go("scenarios/gas_thresholds.scen.json");
+}
+
+#[test]
+fn global_operation_progress_go() {
+    multiversx_sc_scenario::run_go("scenarios/global_operation_progress.scen.json");
+}
+
 #[test]
 fn increase_delegation_cap_go() {
     multiversx_sc_scenario::run_go("scenarios/increase_delegation_cap.scen.json");
 }
 
+#[test]
+fn keeper_bounty_go() {
+    multiversx_sc_scenario::run_go("scenarios/keeper_bounty.scen.json");
+}
+
+#[test]
+fn keeper_bounty_other_endpoints_go() {
+    multiversx_sc_scenario::run_go("scenarios/keeper_bounty_other_endpoints.scen.json");
+}
+
+#[test]
+fn liquid_staking_cap_decrease_go() {
+    multiversx_sc_scenario::run_go("scenarios/liquid_staking_cap_decrease.scen.json");
+}
+
+#[test]
+fn liquid_staking_go() {
+    multiversx_sc_scenario::run_go("scenarios/liquid_staking.scen.json");
+}
+
+#[test]
+fn liquid_staking_tokenize_go() {
+    multiversx_sc_scenario::run_go("scenarios/liquid_staking_tokenize.scen.json");
+}
+
+#[test]
+fn max_user_stake_go() {
+    multiversx_sc_scenario::run_go("scenarios/max_user_stake.scen.json");
+}
+
+#[test]
+fn minimum_stake_go() {
+    multiversx_sc_scenario::run_go("scenarios/minimum_stake.scen.json");
+}
+
+#[test]
+fn node_state_events_go() {
+    multiversx_sc_scenario::run_go("scenarios/node_state_events.scen.json");
+}
+
+#[test]
+fn paginated_views_go() {
+    multiversx_sc_scenario::run_go("scenarios/paginated_views.scen.json");
+}
+
+#[test]
+fn redelegate_rewards_go() {
+    multiversx_sc_scenario::run_go("scenarios/redelegate_rewards.scen.json");
+}
+
 #[test]
 fn rewards_for_unstaked_go_to_the_owner_go() {
     multiversx_sc_scenario::run_go("scenarios/rewards_for_unStaked_go_to_the_owner.scen.json");
 }
 
+#[test]
+fn roles_go() {
+    multiversx_sc_scenario::run_go("scenarios/roles.scen.json");
+}
+
+#[test]
+fn service_fee_announcement_go() {
+    multiversx_sc_scenario::run_go("scenarios/service_fee_announcement.scen.json");
+}
+
+#[test]
+fn service_fee_policy_go() {
+    multiversx_sc_scenario::run_go("scenarios/service_fee_policy.scen.json");
+}
+
 #[test]
 fn set_num_blocks_before_unbond_go() {
     multiversx_sc_scenario::run_go("scenarios/set_num_blocks_before_unbond.scen.json");
@@ -68,11 +183,21 @@ fn staking_2_go() {
     multiversx_sc_scenario::run_go("scenarios/staking_2.scen.json");
 }
 
+#[test]
+fn top_up_stake_go() {
+    multiversx_sc_scenario::run_go("scenarios/top_up_stake.scen.json");
+}
+
 #[test]
 fn total_funds_getters_go() {
     multiversx_sc_scenario::run_go("scenarios/total_funds_getters.scen.json");
 }
 
+#[test]
+fn transfer_active_stake_go() {
+    multiversx_sc_scenario::run_go("scenarios/transfer_active_stake.scen.json");
+}
+
 #[test]
 fn unbond_go() {
     multiversx_sc_scenario::run_go("scenarios/unbond.scen.json");
@@ -113,6 +238,11 @@ fn unstake_5_backwards_go() {
     multiversx_sc_scenario::run_go("scenarios/unstake_5_backwards.scen.json");
 }
 
+#[test]
+fn unstake_tokens_go() {
+    multiversx_sc_scenario::run_go("scenarios/unstake_tokens.scen.json");
+}
+
 #[test]
 fn user_fund_getters_go() {
     multiversx_sc_scenario::run_go("scenarios/user_fund_getters.scen.json");
@@ -122,3 +252,38 @@ fn user_fund_getters_go() {
 fn version_go() {
     multiversx_sc_scenario::run_go("scenarios/version.scen.json");
 }
+
+#[test]
+fn waiting_policy_fifo_go() {
+    multiversx_sc_scenario::run_go("scenarios/waiting_policy_fifo.scen.json");
+}
+
+#[test]
+fn waiting_policy_largest_first_go() {
+    multiversx_sc_scenario::run_go("scenarios/waiting_policy_largest_first.scen.json");
+}
+
+#[test]
+fn waiting_policy_owner_first_go() {
+    multiversx_sc_scenario::run_go("scenarios/waiting_policy_owner_first.scen.json");
+}
+
+#[test]
+fn waiting_policy_pro_rata_go() {
+    multiversx_sc_scenario::run_go("scenarios/waiting_policy_pro_rata.scen.json");
+}
+
+#[test]
+fn waiting_policy_window_go() {
+    multiversx_sc_scenario::run_go("scenarios/waiting_policy_window.scen.json");
+}
+
+#[test]
+fn waiting_queue_position_go() {
+    multiversx_sc_scenario::run_go("scenarios/waiting_queue_position.scen.json");
+}
+
+#[test]
+fn withdraw_waiting_go() {
+    multiversx_sc_scenario::run_go("scenarios/withdraw_waiting.scen.json");
+}
@@ -14,11 +14,31 @@ fn world() -> ScenarioWorld {
     blockchain
 }
 
+#[test]
+fn abort_global_operation_rs() {
+    multiversx_sc_scenario::run_rs("scenarios/abort_global_operation.scen.json", world());
+}
+
 #[test]
 fn activate_nodes_rs() {
     multiversx_sc_scenario::run_rs("scenarios/activate_nodes.scen.json", world());
 }
 
+#[test]
+fn allow_list_rs() {
+    multiversx_sc_scenario::run_rs("scenarios/allow_list.scen.json", world());
+}
+
+#[test]
+fn auto_compound_rs() {
+    multiversx_sc_scenario::run_rs("scenarios/auto_compound.scen.json", world());
+}
+
+#[test]
+fn auto_compound_unstake_rs() {
+    multiversx_sc_scenario::run_rs("scenarios/auto_compound_unstake.scen.json", world());
+}
+
 #[test]
 fn change_service_fee_rs() {
     multiversx_sc_scenario::run_rs("scenarios/change_service_fee.scen.json", world());
@@ -37,6 +57,11 @@ fn claim_rewards_owner_with_stake_rs() {
     );
 }
 
+#[test]
+fn claim_rewards_to_rs() {
+    multiversx_sc_scenario::run_rs("scenarios/claim_rewards_to.scen.json", world());
+}
+
 #[test]
 fn claim_rewards_with_changed_service_fee_rs() {
     multiversx_sc_scenario::run_rs(
@@ -66,6 +91,11 @@ fn continue_global_operations_rs() {
     multiversx_sc_scenario::run_rs("scenarios/continue_global_operations.scen.json", world());
 }
 
+#[test]
+fn contract_health_rs() {
+    multiversx_sc_scenario::run_rs("scenarios/contract_health.scen.json", world());
+}
+
 #[test]
 fn decrease_cap_in_bootstrap_mode_rs() {
     multiversx_sc_scenario::run_rs(
@@ -74,11 +104,81 @@ fn decrease_cap_in_bootstrap_mode_rs() {
     );
 }
 
+#[test]
+fn delegator_info_rs() {
+    multiversx_sc_scenario::run_rs("scenarios/delegator_info.scen.json", world());
+}
+
+#[test]
+fn fund_movement_events_rs() {
+    multiversx_sc_scenario::run_rs("scenarios/fund_movement_events.scen.json", world());
+}
+
+#[test]
+fn gas_thresholds_rs() {
+    multiversx_sc_scenario::run_rs("scenarios/gas_thresholds.scen.json", world());
+}
+
+#[test]
+fn global_operation_progress_rs() {
+    multiversx_sc_scenario::run_rs("scenarios/global_operation_progress.scen.json", world());
+}
+
 #[test]
 fn increase_delegation_cap_rs() {
     multiversx_sc_scenario::run_rs("scenarios/increase_delegation_cap.scen.json", world());
 }
 
+#[test]
+fn keeper_bounty_other_endpoints_rs() {
+    multiversx_sc_scenario::run_rs("scenarios/keeper_bounty_other_endpoints.scen.json", world());
+}
+
+#[test]
+fn keeper_bounty_rs() {
+    multiversx_sc_scenario::run_rs("scenarios/keeper_bounty.scen.json", world());
+}
+
+#[test]
+fn liquid_staking_cap_decrease_rs() {
+    multiversx_sc_scenario::run_rs("scenarios/liquid_staking_cap_decrease.scen.json", world());
+}
+
+#[test]
+fn liquid_staking_rs() {
+    multiversx_sc_scenario::run_rs("scenarios/liquid_staking.scen.json", world());
+}
+
+#[test]
+fn liquid_staking_tokenize_rs() {
+    multiversx_sc_scenario::run_rs("scenarios/liquid_staking_tokenize.scen.json", world());
+}
+
+#[test]
+fn max_user_stake_rs() {
+    multiversx_sc_scenario::run_rs("scenarios/max_user_stake.scen.json", world());
+}
+
+#[test]
+fn minimum_stake_rs() {
+    multiversx_sc_scenario::run_rs("scenarios/minimum_stake.scen.json", world());
+}
+
+#[test]
+fn node_state_events_rs() {
+    multiversx_sc_scenario::run_rs("scenarios/node_state_events.scen.json", world());
+}
+
+#[test]
+fn paginated_views_rs() {
+    multiversx_sc_scenario::run_rs("scenarios/paginated_views.scen.json", world());
+}
+
+#[test]
+fn redelegate_rewards_rs() {
+    multiversx_sc_scenario::run_rs("scenarios/redelegate_rewards.scen.json", world());
+}
+
 #[test]
 fn rewards_for_unstaked_go_to_the_owner_rs() {
     multiversx_sc_scenario::run_rs(
@@ -87,6 +187,21 @@ fn rewards_for_unstaked_go_to_the_owner_rs() {
     );
 }
 
+#[test]
+fn roles_rs() {
+    multiversx_sc_scenario::run_rs("scenarios/roles.scen.json", world());
+}
+
+#[test]
+fn service_fee_announcement_rs() {
+    multiversx_sc_scenario::run_rs("scenarios/service_fee_announcement.scen.json", world());
+}
+
+#[test]
+fn service_fee_policy_rs() {
+    multiversx_sc_scenario::run_rs("scenarios/service_fee_policy.scen.json", world());
+}
+
 #[test]
 fn set_num_blocks_before_unbond_rs() {
     multiversx_sc_scenario::run_rs("scenarios/set_num_blocks_before_unbond.scen.json", world());
@@ -102,11 +217,21 @@ fn staking_2_rs() {
     multiversx_sc_scenario::run_rs("scenarios/staking_2.scen.json", world());
 }
 
+#[test]
+fn top_up_stake_rs() {
+    multiversx_sc_scenario::run_rs("scenarios/top_up_stake.scen.json", world());
+}
+
 #[test]
 fn total_funds_getters_rs() {
     multiversx_sc_scenario::run_rs("scenarios/total_funds_getters.scen.json", world());
 }
 
+#[test]
+fn transfer_active_stake_rs() {
+    multiversx_sc_scenario::run_rs("scenarios/transfer_active_stake.scen.json", world());
+}
+
 #[test]
 fn unbond_rs() {
     multiversx_sc_scenario::run_rs("scenarios/unbond.scen.json", world());
@@ -147,6 +272,11 @@ fn unstake_5_backwards_rs() {
     multiversx_sc_scenario::run_rs("scenarios/unstake_5_backwards.scen.json", world());
 }
 
+#[test]
+fn unstake_tokens_rs() {
+    multiversx_sc_scenario::run_rs("scenarios/unstake_tokens.scen.json", world());
+}
+
 #[test]
 fn user_fund_getters_rs() {
     multiversx_sc_scenario::run_rs("scenarios/user_fund_getters.scen.json", world());
@@ -156,3 +286,38 @@ fn user_fund_getters_rs() {
 fn version_rs() {
     multiversx_sc_scenario::run_rs("scenarios/version.scen.json", world());
 }
+
+#[test]
+fn waiting_policy_fifo_rs() {
+    multiversx_sc_scenario::run_rs("scenarios/waiting_policy_fifo.scen.json", world());
+}
+
+#[test]
+fn waiting_policy_largest_first_rs() {
+    multiversx_sc_scenario::run_rs("scenarios/waiting_policy_largest_first.scen.json", world());
+}
+
+#[test]
+fn waiting_policy_owner_first_rs() {
+    multiversx_sc_scenario::run_rs("scenarios/waiting_policy_owner_first.scen.json", world());
+}
+
+#[test]
+fn waiting_policy_pro_rata_rs() {
+    multiversx_sc_scenario::run_rs("scenarios/waiting_policy_pro_rata.scen.json", world());
+}
+
+#[test]
+fn waiting_policy_window_rs() {
+    multiversx_sc_scenario::run_rs("scenarios/waiting_policy_window.scen.json", world());
+}
+
+#[test]
+fn waiting_queue_position_rs() {
+    multiversx_sc_scenario::run_rs("scenarios/waiting_queue_position.scen.json", world());
+}
+
+#[test]
+fn withdraw_waiting_rs() {
+    multiversx_sc_scenario::run_rs("scenarios/withdraw_waiting.scen.json", world());
+}
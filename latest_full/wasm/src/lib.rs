@@ -5,9 +5,9 @@
 ////////////////////////////////////////////////////
 
 // Init:                                 1
-// Endpoints:                           79
+// Endpoints:                          168
 // Async Callback:                       1
-// Total number of exported functions:  81
+// Total number of exported functions: 170
 
 #![no_std]
 #![feature(alloc_error_handler, lang_items)]
@@ -24,6 +24,7 @@ multiversx_sc_wasm_adapter::endpoints! {
         getNodeSignature
         getNodeState
         getAllNodeStates
+        getNodeStatesPage
         getNodeBlockNonceOfUnstake
         addNodes
         removeNodes
@@ -32,6 +33,7 @@ multiversx_sc_wasm_adapter::endpoints! {
         getNumUsers
         updateUserAddress
         userIdsWithoutAddress
+        userIdsWithoutAddressPage
         fundById
         totalStake
         getUserStake
@@ -48,9 +50,12 @@ multiversx_sc_wasm_adapter::endpoints! {
         getUserStakeByType
         getTotalStakeByType
         getAllUserStakeByType
+        getAllUserStakeByTypePage
         getUserDeferredPaymentList
         getFullWaitingList
+        getWaitingListPage
         getFullActiveList
+        getActiveListPage
         stakeNodes
         unStakeNodes
         unStakeNodesAndTokens
@@ -59,10 +64,25 @@ multiversx_sc_wasm_adapter::endpoints! {
         unBondAllPossibleNodes
         claimUnusedFunds
         unJailNodes
+        getAuctionStakedTokens
+        getNodeAuctionStake
+        getAuctionUnstakedTokens
+        getAuctionTopUp
+        forceAuctionStakedTokens
+        stakeTopUp
+        unStakeTopUp
         unStakeTokens
         unBondTokens
         getAuctionContractAddress
         getServiceFee
+        getMinServiceFee
+        getMaxServiceFee
+        setServiceFeeBounds
+        getServiceFeeMaxChange
+        setServiceFeeMaxChange
+        getServiceFeeNoticeBlocks
+        setServiceFeeNoticeBlocks
+        getPendingServiceFee
         getTotalDelegationCap
         isBootstrapMode
         getOwnerMinStakeShare
@@ -70,20 +90,70 @@ multiversx_sc_wasm_adapter::endpoints! {
         setNumBlocksBeforeUnBond
         getMinimumStake
         setMinimumStake
+        getMaximumUserStake
+        setMaximumUserStake
+        getWaitingActivationPolicy
+        setWaitingActivationPolicy
+        getWaitingPolicyWindowSize
+        getAutoCompoundMinAmount
+        setAutoCompoundMinAmount
+        getGlobalOpGasThreshold
+        setGlobalOpGasThreshold
+        getDustCleanupGasThreshold
+        setDustCleanupGasThreshold
+        getUnBondGasThreshold
+        setUnBondGasThreshold
+        getAutoCompoundGasThreshold
+        setAutoCompoundGasThreshold
+        getKeeperServiceFeeShare
+        isAllowListEnabled
+        setAllowListEnabled
+        getAllowListManager
+        setAllowListManager
+        isAllowListed
+        addToAllowList
+        removeFromAllowList
+        canStake
         getGlobalOperationCheckpoint
         isGlobalOperationInProgress
+        getLegacyRewardsSnapshot
         getTotalCumulatedRewards
+        getRewardIndex
         getClaimableRewards
         getTotalUnclaimedRewards
+        getTotalUnclaimedRewardsPage
         getTotalUnProtected
+        getUserStakeHeadroom
         validateOwnerStakeShare
         validateDelegationCapInvariant
+        getKeeperPool
+        getKeeperBounty
+        getKeeperMinBlocksBetweenBounties
+        getKeeperNextBountyBlock
+        getKeeperDustLimit
+        setKeeperBounty
+        setKeeperServiceFeeShare
+        setKeeperDustLimit
+        fundKeeperPool
+        withdrawKeeperPool
         continueGlobalOperation
+        abortGlobalOperation
+        getGlobalOperationProgress
         modifyTotalDelegationCap
+        announceServiceFee
+        cancelServiceFeeAnnouncement
         setServiceFee
         claimRewards
+        claimRewardsTo
+        reDelegateRewards
+        autoCompoundCheckpoint
+        autoCompoundRewards
         stake
         unStake
+        withdrawWaiting
+        transferActiveStake
+        setAutoCompound
+        isAutoCompoundEnabled
         getUnStakeable
         unBond
         getUnBondable
@@ -92,6 +162,25 @@ multiversx_sc_wasm_adapter::endpoints! {
         countDustItemsActive
         dustCleanupWaitingList
         dustCleanupActive
+        getLiquidStakingTokenId
+        getLiquidStakingTokenSupply
+        getLiquidStakingPoolActiveStake
+        isLiquidStakingActive
+        issueLiquidStakingToken
+        setLiquidStakingTokenRoles
+        setLiquidStaking
+        isLiquidStakingEnabled
+        getPendingLiquidStake
+        claimLiquidStakingTokens
+        unwrapActiveStake
+        grantRole
+        revokeRole
+        hasRole
+        pauseContract
+        getDelegatorInfo
+        getWaitingQueuePosition
+        getContractHealth
+        getTopUpReconciliation
         dnsRegister
         setFeatureFlag
         pause
@@ -1,6 +1,8 @@
 #![no_std]
 #![allow(clippy::string_lit_as_bytes)]
 
+use delegation_latest::node_storage::types::NodeState;
+use delegation_latest::settings::DEFAULT_SERVICE_FEE_NOTICE_BLOCKS;
 use delegation_latest::user_fund_storage::fund_view_module::USER_STAKE_TOTALS_ID;
 use delegation_latest::user_fund_storage::types::FundType;
 
@@ -19,10 +21,16 @@ pub trait DelegationUpdate:
     + delegation_latest::rewards_state::RewardStateModule
     + delegation_latest::user_stake_state::UserStakeStateModule
     + delegation_latest::events::EventsModule
+    + delegation_latest::keeper_bounty::KeeperBountyModule
     + delegation_latest::reset_checkpoint_endpoints::ResetCheckpointsModule
     + delegation_latest::rewards_endpoints::RewardEndpointsModule
     + delegation_latest::user_stake_endpoints::UserStakeEndpointsModule
     + delegation_latest::user_stake_dust_cleanup::UserStakeDustCleanupModule
+    + delegation_latest::liquid_staking_state::LiquidStakingStateModule
+    + delegation_latest::liquid_staking_endpoints::LiquidStakingEndpointsModule
+    + delegation_latest::roles::RolesModule
+    + delegation_latest::delegator_info::DelegatorInfoModule
+    + delegation_latest::contract_health::ContractHealthModule
     + delegation_latest::multiversx_sc_modules::dns::DnsModule
     + delegation_latest::multiversx_sc_modules::features::FeaturesModule
     + delegation_latest::multiversx_sc_modules::pause::PauseModule
@@ -54,5 +62,44 @@ pub trait DelegationUpdate:
     #[init]
     fn init(&self) {
         self.update_total_delegation_cap_if_necessary();
+
+        // rewards are no longer recomputed for all delegators when the service fee or the delegation cap change
+        self.init_reward_index();
+
+        // service fee changes were previously applied immediately
+        if self.service_fee_notice_blocks().is_empty() {
+            self.service_fee_notice_blocks()
+                .set(DEFAULT_SERVICE_FEE_NOTICE_BLOCKS);
+        }
+
+        self.update_auction_staked_tokens_if_necessary();
+    }
+
+    /// The stake held by the auction contract was not tracked before,
+    /// without it `unStakeTokens` would reject any amount.
+    /// Before tracking, the nodes held exactly the active and unstaked stake,
+    /// the same as the total delegation cap set above.
+    fn update_auction_staked_tokens_if_necessary(&self) {
+        if !self.auction_staked_tokens().is_empty() {
+            return;
+        }
+
+        let num_nodes = self.num_nodes().get();
+        let any_node_in_auction = (1..=num_nodes).any(|node_id| {
+            matches!(
+                self.get_node_state(node_id),
+                NodeState::Active
+                    | NodeState::PendingDeactivation
+                    | NodeState::UnBondPeriod { .. }
+                    | NodeState::PendingUnBond { .. }
+            )
+        });
+        if any_node_in_auction {
+            let total_active = self.get_user_stake_of_type(USER_STAKE_TOTALS_ID, FundType::Active);
+            let total_unstaked =
+                self.get_user_stake_of_type(USER_STAKE_TOTALS_ID, FundType::UnStaked);
+            self.auction_staked_tokens()
+                .set(&(total_active + total_unstaked));
+        }
     }
 }
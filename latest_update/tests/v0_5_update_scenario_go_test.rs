@@ -1,8 +1,18 @@
+#[test]
+fn abort_legacy_global_operation_go() {
+    multiversx_sc_scenario::run_go("scenarios/abort_legacy_global_operation.scen.json");
+}
+
 #[test]
 fn genesis_addr_fix_go() {
     multiversx_sc_scenario::run_go("scenarios/genesis_addr_fix.scen.json");
 }
 
+#[test]
+fn reward_index_migration_go() {
+    multiversx_sc_scenario::run_go("scenarios/reward_index_migration.scen.json");
+}
+
 #[test]
 fn version_go() {
     multiversx_sc_scenario::run_go("scenarios/version.scen.json");
@@ -14,11 +14,21 @@ fn world() -> ScenarioWorld {
     blockchain
 }
 
+#[test]
+fn abort_legacy_global_operation_rs() {
+    multiversx_sc_scenario::run_rs("scenarios/abort_legacy_global_operation.scen.json", world());
+}
+
 #[test]
 fn genesis_addr_fix() {
     multiversx_sc_scenario::run_rs("scenarios/genesis_addr_fix.scen.json", world());
 }
 
+#[test]
+fn reward_index_migration_rs() {
+    multiversx_sc_scenario::run_rs("scenarios/reward_index_migration.scen.json", world());
+}
+
 #[test]
 fn version_rs() {
     multiversx_sc_scenario::run_rs("scenarios/version.scen.json", world());
@@ -6,6 +6,9 @@ pub const MAX_NODES_PER_OPERATION: usize = 100;
 
 pub type NodeIndexArrayVec = ArrayVec<usize, MAX_NODES_PER_OPERATION>;
 
+/// Node ids, together with the error code the auction contract returned for each of them.
+pub type NodeErrorArrayVec = ArrayVec<(usize, u32), MAX_NODES_PER_OPERATION>;
+
 /// Indicates how we express the percentage of rewards that go to the node.
 /// Since we cannot have floating point numbers, we use fixed point with this denominator.
 /// Percents + 2 decimals -> 10000.
@@ -89,6 +92,29 @@ pub trait NodeConfigModule {
         result
     }
 
+    /// Paginated version of `getAllNodeStates`. Pages start with a node id, node ids start from 1.
+    /// Yields the id where the next page starts (0 if there are no more pages), followed by the node states.
+    #[view(getNodeStatesPage)]
+    fn get_node_states_page(
+        &self,
+        start_id: usize,
+        limit: usize,
+    ) -> MultiValue2<usize, MultiValueEncoded<MultiValue2<BLSKey<Self::Api>, u8>>> {
+        require!(limit > 0, "page limit must be positive");
+        let num_nodes = self.num_nodes().get();
+        let first_id = core::cmp::max(start_id, 1);
+        let last_id = core::cmp::min(first_id.saturating_add(limit - 1), num_nodes);
+        let mut result = MultiValueEncoded::new();
+        for i in first_id..=last_id {
+            result.push(MultiValue2::from((
+                self.get_node_id_to_bls(i),
+                self.get_node_state(i).discriminant(),
+            )));
+        }
+        let next_id = if last_id < num_nodes { last_id + 1 } else { 0 };
+        (next_id, result).into()
+    }
+
     #[view(getNodeBlockNonceOfUnstake)]
     fn get_node_bl_nonce_of_unstake_endpoint(
         &self,
@@ -121,10 +147,10 @@ pub trait NodeConfigModule {
                 node_id = num_nodes;
                 self.set_node_bls_to_id(&bls_key, node_id);
                 self.set_node_id_to_bls(node_id, &bls_key);
-                self.set_node_state(node_id, NodeState::Inactive);
+                self.change_node_state(node_id, NodeState::Inactive, 0);
                 self.set_node_signature(node_id, bls_sig);
             } else if self.get_node_state(node_id) == NodeState::Removed {
-                self.set_node_state(node_id, NodeState::Inactive);
+                self.change_node_state(node_id, NodeState::Inactive, 0);
                 self.set_node_signature(node_id, bls_sig);
             } else {
                 sc_panic!("node already registered");
@@ -143,7 +169,7 @@ pub trait NodeConfigModule {
                 self.get_node_state(node_id) == NodeState::Inactive,
                 "only inactive nodes can be removed"
             );
-            self.set_node_state(node_id, NodeState::Removed);
+            self.change_node_state(node_id, NodeState::Removed, 0);
         }
     }
 
@@ -151,8 +177,8 @@ pub trait NodeConfigModule {
         &self,
         mut node_ids: NodeIndexArrayVec,
         node_status_args: MultiValueEncoded<BLSStatusMultiArg<Self::Api>>,
-    ) -> (NodeIndexArrayVec, NodeIndexArrayVec) {
-        let mut failed_node_ids: NodeIndexArrayVec = NodeIndexArrayVec::new();
+    ) -> (NodeIndexArrayVec, NodeErrorArrayVec) {
+        let mut failed_nodes = NodeErrorArrayVec::new();
         for arg in node_status_args.into_iter() {
             let (bls_key, status) = arg.into_tuple();
             if status != 0 {
@@ -160,11 +186,36 @@ pub trait NodeConfigModule {
                 // move node from ok nodes to failed ones
                 if let Some(pos) = node_ids.iter().position(|x| *x == node_id) {
                     node_ids.swap_remove(pos);
-                    failed_node_ids.push(node_id);
+                    failed_nodes.push((node_id, status));
                 }
             }
         }
 
-        (node_ids, failed_node_ids)
+        (node_ids, failed_nodes)
     }
+
+    /// Changes the state of a node and logs the transition.
+    /// The error code is the one returned by the auction contract for the node, if any, 0 otherwise.
+    fn change_node_state(&self, node_id: usize, new_state: NodeState, error_code: u32) {
+        let old_state = self.get_node_state(node_id);
+        self.set_node_state(node_id, new_state);
+
+        self.node_state_change_event(
+            &self.get_node_id_to_bls(node_id),
+            &old_state,
+            &new_state,
+            self.blockchain().get_block_nonce(),
+            error_code,
+        );
+    }
+
+    #[event("nodeStateChange")]
+    fn node_state_change_event(
+        &self,
+        #[indexed] bls_key: &BLSKey<Self::Api>,
+        #[indexed] old_state: &NodeState,
+        #[indexed] new_state: &NodeState,
+        #[indexed] block_nonce: u64,
+        error_code: u32,
+    );
 }
@@ -5,6 +5,7 @@ use crate::types::{
     affected_users_sort_dedup, AffectedUserIdVec, FundDescription, FundItem, FundType,
     FundsListInfo,
 };
+use crate::user_data;
 
 #[derive(PartialEq, Eq, Clone, Copy)]
 pub enum SwapDirection {
@@ -14,7 +15,7 @@ pub enum SwapDirection {
 
 /// Deals with storage data about delegators.
 #[multiversx_sc::derive::module]
-pub trait FundModule {
+pub trait FundModule: user_data::UserDataModule {
     #[view(fundById)]
     #[storage_mapper("f")]
     fn fund_by_id(&self, id: usize) -> SingleValueMapper<FundItem<Self::Api>>;
@@ -39,6 +40,85 @@ pub trait FundModule {
         fund_type: FundType,
     ) -> SingleValueMapper<FundsListInfo<Self::Api>>;
 
+    /// Logged every time funds of a user change type, so that balances can be followed without polling.
+    #[event("fundMovement")]
+    fn fund_movement_event(
+        &self,
+        #[indexed] user: &ManagedAddress,
+        #[indexed] source_type: FundType,
+        #[indexed] target_type: FundType,
+        amount: &BigUint,
+    );
+
+    /// Same as `fundMovement`, for users without an address entry, e.g. left over from genesis.
+    #[event("fundMovementByUserId")]
+    fn fund_movement_by_user_id_event(
+        &self,
+        #[indexed] user_id: usize,
+        #[indexed] source_type: FundType,
+        #[indexed] target_type: FundType,
+        amount: &BigUint,
+    );
+
+    /// Logged when funds change owner without changing type, e.g. when active stake is transferred.
+    #[event("fundTransfer")]
+    fn fund_transfer_event(
+        &self,
+        #[indexed] from: &ManagedAddress,
+        #[indexed] to: &ManagedAddress,
+        #[indexed] fund_type: FundType,
+        amount: &BigUint,
+    );
+
+    /// Same as `fundTransfer`, when either user has no address entry.
+    #[event("fundTransferByUserId")]
+    fn fund_transfer_by_user_id_event(
+        &self,
+        #[indexed] from_user_id: usize,
+        #[indexed] to_user_id: usize,
+        #[indexed] fund_type: FundType,
+        amount: &BigUint,
+    );
+
+    fn log_fund_transfer(
+        &self,
+        from_user_id: usize,
+        to_user_id: usize,
+        fund_type: FundType,
+        amount: &BigUint,
+    ) {
+        if *amount == 0 {
+            return;
+        }
+
+        if self.is_empty_user_address(from_user_id) || self.is_empty_user_address(to_user_id) {
+            self.fund_transfer_by_user_id_event(from_user_id, to_user_id, fund_type, amount);
+        } else {
+            let from = self.get_user_address(from_user_id);
+            let to = self.get_user_address(to_user_id);
+            self.fund_transfer_event(&from, &to, fund_type, amount);
+        }
+    }
+
+    fn log_fund_movement(
+        &self,
+        user_id: usize,
+        source_type: FundType,
+        target_type: FundType,
+        amount: &BigUint,
+    ) {
+        if *amount == 0 {
+            return;
+        }
+
+        if self.is_empty_user_address(user_id) {
+            self.fund_movement_by_user_id_event(user_id, source_type, target_type, amount);
+        } else {
+            let user_address = self.get_user_address(user_id);
+            self.fund_movement_event(&user_address, source_type, target_type, amount);
+        }
+    }
+
     /// For testing; please do not use in production.
     /// Goes through all fund items, ignores indexes.
     fn query_sum_all_funds_brute_force<F>(&self, filter: F) -> BigUint
@@ -140,6 +220,41 @@ pub trait FundModule {
         }
     }
 
+    /// Same as `foreach_fund_by_type` going forwards, but visits at most `limit` items,
+    /// starting with the item with id `start_id`, or with the first item of the list if `start_id` is 0.
+    /// Returns the id where the next page starts, or 0 if the end of the list was reached.
+    fn foreach_fund_by_type_page<F>(
+        &self,
+        fund_type: FundType,
+        start_id: usize,
+        limit: usize,
+        mut closure: F,
+    ) -> usize
+    where
+        F: FnMut(FundItem<Self::Api>),
+    {
+        let mut id = if start_id == 0 {
+            self.get_fund_list_by_type(fund_type).first
+        } else {
+            // the item might have been consumed since the previous page was retrieved
+            require!(
+                !self.fund_by_id(start_id).is_empty()
+                    && self.fund_by_id(start_id).get().fund_desc.fund_type() == fund_type,
+                "invalid page start id"
+            );
+            start_id
+        };
+        let mut num_visited = 0usize;
+        while id > 0 && num_visited < limit {
+            let fund_item = self.fund_by_id(id).get();
+            let next_id = fund_item.type_list_next;
+            closure(fund_item);
+            id = next_id;
+            num_visited += 1;
+        }
+        id
+    }
+
     fn count_fund_items_by_type<F>(&self, fund_type: FundType, filter: F) -> usize
     where
         F: Fn(&FundItem<Self::Api>) -> bool,
@@ -397,11 +512,18 @@ pub trait FundModule {
         transformed: FundDescription,
         fund_item: &mut FundItem<Self::Api>,
     ) {
+        let source_type = fund_item.fund_desc.fund_type();
         let extracted_balance = if let Some(max_amount) = opt_max_amount {
             self.decrease_fund_balance(max_amount, &mut *fund_item)
         } else {
             self.delete_fund(&mut *fund_item)
         };
+        self.log_fund_movement(
+            fund_item.user_id,
+            source_type,
+            transformed.fund_type(),
+            &extracted_balance,
+        );
         // create / increase
         self.increase_fund_balance(fund_item.user_id, transformed, extracted_balance);
     }
@@ -555,6 +677,12 @@ pub trait FundModule {
                 }
                 // add to sum
                 total_transformed += &extracted_balance;
+                self.log_fund_movement(
+                    user_id,
+                    source_type,
+                    transformed.fund_type(),
+                    &extracted_balance,
+                );
                 // create / increase
                 self.increase_fund_balance(fund_item.user_id, transformed, extracted_balance);
             }
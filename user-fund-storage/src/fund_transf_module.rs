@@ -2,10 +2,18 @@ multiversx_sc::imports!();
 
 use crate::fund_module::{self, SwapDirection};
 use crate::types::{AffectedUserIdVec, FundDescription, FundItem, FundType};
+use crate::user_data;
+
+/// How many of the largest waiting entries are picked in one pass over the waiting list.
+pub const LARGEST_FIRST_BATCH_SIZE: usize = 20;
+
+/// How many entries from the front of the waiting list the activation policies look at in one pass,
+/// so that the cost of a pass does not grow with the length of the waiting list.
+pub const WAITING_POLICY_WINDOW_SIZE: usize = 50;
 
 /// Deals with storage data about delegators.
 #[multiversx_sc::derive::module]
-pub trait FundTransformationsModule: fund_module::FundModule {
+pub trait FundTransformationsModule: fund_module::FundModule + user_data::UserDataModule {
     fn create_waiting(&self, user_id: usize, balance: BigUint) {
         let current_bl_nonce = self.blockchain().get_block_nonce();
         self.increase_fund_balance(
@@ -41,21 +49,236 @@ pub trait FundTransformationsModule: fund_module::FundModule {
         );
     }
 
+    /// Moves active stake from one user to another, starting with the most recent entries.
+    /// The stake remains active the whole time, only its owner changes.
+    fn transfer_user_active(&self, from_user_id: usize, to_user_id: usize, amount: &mut BigUint) {
+        let mut id =
+            self.first_id_of_user_type(from_user_id, FundType::Active, SwapDirection::Backwards);
+        let mut transferred = BigUint::zero();
+
+        while id > 0 && *amount > 0 {
+            let mut fund_item = self.fund_by_id(id).get();
+            let next_id = fund_item.user_list_prev; // save next id now, because fund_item can be destroyed
+
+            let extracted_balance = self.decrease_fund_balance(amount, &mut fund_item);
+            self.fund_by_id(id).set(&fund_item);
+
+            transferred += &extracted_balance;
+            self.increase_fund_balance(to_user_id, FundDescription::Active, extracted_balance);
+            id = next_id;
+        }
+
+        self.log_fund_transfer(from_user_id, to_user_id, FundType::Active, &transferred);
+    }
+
     fn swap_waiting_to_active<I: Fn() -> bool>(
         &self,
         remaining: &mut BigUint,
         interrupt: I,
     ) -> AffectedUserIdVec {
+        self.swap_waiting_to_active_with_hook(remaining, |_| {}, interrupt)
+    }
+
+    /// Same as `swap_waiting_to_active`, but calls `before_swap` with the owner of each entry,
+    /// right before the entry is activated.
+    /// The hook must not create or move fund items, since the waiting list is being traversed.
+    fn swap_waiting_to_active_with_hook<B, I>(
+        &self,
+        remaining: &mut BigUint,
+        mut before_swap: B,
+        interrupt: I,
+    ) -> AffectedUserIdVec
+    where
+        B: FnMut(usize),
+        I: Fn() -> bool,
+    {
         self.split_convert_max_by_type(
             Some(remaining),
             FundType::Waiting,
             SwapDirection::Forwards,
-            |_| Some(FundDescription::Active),
+            |fund_item| {
+                before_swap(fund_item.user_id);
+                Some(FundDescription::Active)
+            },
             interrupt,
             false,
         )
     }
 
+    fn swap_user_waiting_to_active(&self, user_id: usize, remaining: &mut BigUint) {
+        let _ = self.split_convert_max_by_user(
+            Some(remaining),
+            user_id,
+            FundType::Waiting,
+            SwapDirection::Forwards,
+            |_| Some(FundDescription::Active),
+            || false,
+        );
+    }
+
+    /// Activates the largest waiting entries first. Entries of equal size are activated in order.
+    /// Each pass looks at the first `WAITING_POLICY_WINDOW_SIZE` entries of the waiting list and picks the largest of them,
+    /// only as many as needed to cover the remaining amount, and at most `LARGEST_FIRST_BATCH_SIZE` of them.
+    /// If interrupted in the middle of a pass, only the largest entries seen so far are activated.
+    fn swap_waiting_to_active_largest_first<B, I>(
+        &self,
+        remaining: &mut BigUint,
+        mut before_swap: B,
+        interrupt: I,
+    ) where
+        B: FnMut(usize),
+        I: Fn() -> bool,
+    {
+        loop {
+            if *remaining == 0 {
+                return;
+            }
+
+            // sorted by balance, largest first
+            let mut batch: ArrayVec<(usize, BigUint), LARGEST_FIRST_BATCH_SIZE> = ArrayVec::new();
+            let mut batch_total = BigUint::zero();
+            let mut interrupted = false;
+
+            let mut id = self.get_fund_list_by_type(FundType::Waiting).first;
+            let mut num_seen = 0usize;
+            while id > 0 && num_seen < WAITING_POLICY_WINDOW_SIZE {
+                num_seen += 1;
+                if interrupt() {
+                    interrupted = true;
+                    break;
+                }
+
+                let fund_item = self.fund_by_id(id).get();
+                let position = batch
+                    .iter()
+                    .position(|(_, balance)| fund_item.balance > *balance)
+                    .unwrap_or(batch.len());
+                if position < LARGEST_FIRST_BATCH_SIZE {
+                    if batch.is_full() {
+                        if let Some((_, smallest)) = batch.pop() {
+                            batch_total -= smallest;
+                        }
+                    }
+                    batch_total += &fund_item.balance;
+                    batch.insert(position, (id, fund_item.balance.clone()));
+
+                    // drop the smallest entries, as long as the others cover the remaining amount
+                    while batch.len() > 1 {
+                        let (_, smallest) = &batch[batch.len() - 1];
+                        if &batch_total - smallest < *remaining {
+                            break;
+                        }
+                        batch_total -= smallest;
+                        batch.pop();
+                    }
+                }
+                id = fund_item.type_list_next;
+            }
+
+            if batch.is_empty() {
+                return; // waiting list is empty, or interrupted right away
+            }
+
+            for (id, _) in batch.iter() {
+                if *remaining == 0 {
+                    break;
+                }
+                self.fund_by_id(*id).update(|fund_item| {
+                    before_swap(fund_item.user_id);
+                    self.split_convert_individual_fund(
+                        &mut Some(&mut *remaining),
+                        FundDescription::Active,
+                        fund_item,
+                    );
+                });
+            }
+
+            if interrupted {
+                return;
+            }
+        }
+    }
+
+    /// Activates the same proportion of each waiting entry.
+    /// Proportions are computed over the first `WAITING_POLICY_WINDOW_SIZE` entries of the waiting list:
+    /// if these cannot all be activated, they share the remaining amount, otherwise they are all activated
+    /// and the next entries are considered.
+    /// Shares are rounded down, the few leftover units are activated in order.
+    /// If interrupted, the next call splits what remains in proportion to what is then in the waiting list.
+    fn swap_waiting_to_active_pro_rata<B, I>(
+        &self,
+        remaining: &mut BigUint,
+        mut before_swap: B,
+        interrupt: I,
+    ) where
+        B: FnMut(usize),
+        I: Fn() -> bool,
+    {
+        loop {
+            if *remaining == 0 {
+                return;
+            }
+
+            let mut window_ids: ArrayVec<usize, WAITING_POLICY_WINDOW_SIZE> = ArrayVec::new();
+            let mut window_total = BigUint::zero();
+            let mut id = self.get_fund_list_by_type(FundType::Waiting).first;
+            while id > 0 && !window_ids.is_full() {
+                if interrupt() {
+                    return;
+                }
+                let fund_item = self.fund_by_id(id).get();
+                window_total += &fund_item.balance;
+                window_ids.push(id);
+                id = fund_item.type_list_next;
+            }
+
+            if window_ids.is_empty() {
+                return; // waiting list is empty
+            }
+
+            if window_total <= *remaining {
+                // the whole window gets activated, in order
+                let mut window_remaining = window_total.clone();
+                let _ = self.swap_waiting_to_active_with_hook(
+                    &mut window_remaining,
+                    &mut before_swap,
+                    &interrupt,
+                );
+                *remaining -= &(&window_total - &window_remaining);
+                if window_remaining > 0 {
+                    return; // interrupted
+                }
+                continue;
+            }
+
+            let to_activate = remaining.clone();
+            for id in window_ids.iter() {
+                if *remaining == 0 || interrupt() {
+                    break;
+                }
+                self.fund_by_id(*id).update(|fund_item| {
+                    let mut share = &fund_item.balance * &to_activate / &window_total;
+                    if share > *remaining {
+                        share = remaining.clone();
+                    }
+                    if share > 0 {
+                        *remaining -= &share;
+                        before_swap(fund_item.user_id);
+                        self.split_convert_individual_fund(
+                            &mut Some(&mut share),
+                            FundDescription::Active,
+                            fund_item,
+                        );
+                    }
+                });
+            }
+
+            // rounding leftovers
+            let _ = self.swap_waiting_to_active_with_hook(remaining, before_swap, interrupt);
+            return;
+        }
+    }
+
     fn swap_user_waiting_to_withdraw_only(&self, user_id: usize, remaining: &mut BigUint) {
         let _ = self.split_convert_max_by_user(
             Some(remaining),
@@ -137,12 +360,30 @@ pub trait FundTransformationsModule: fund_module::FundModule {
         remaining: &mut BigUint,
         interrupt: I,
     ) {
+        self.swap_active_to_deferred_payment_with_hook(remaining, |_| true, interrupt);
+    }
+
+    /// Same as `swap_active_to_deferred_payment`, but calls `before_swap` with the owner of each entry,
+    /// right before the entry is converted. Entries for which the hook returns false are left active.
+    /// The hook must not create or move fund items, since the active list is being traversed.
+    fn swap_active_to_deferred_payment_with_hook<B, I>(
+        &self,
+        remaining: &mut BigUint,
+        mut before_swap: B,
+        interrupt: I,
+    ) where
+        B: FnMut(usize) -> bool,
+        I: Fn() -> bool,
+    {
         let current_bl_nonce = self.blockchain().get_block_nonce();
         let _ = self.split_convert_max_by_type(
             Some(remaining),
             FundType::Active,
             SwapDirection::Backwards,
-            |_| {
+            |fund_item| {
+                if !before_swap(fund_item.user_id) {
+                    return None;
+                }
                 Some(FundDescription::DeferredPayment {
                     created: current_bl_nonce,
                 })
@@ -16,6 +16,10 @@ pub const USER_STAKE_TOTALS_ID: usize = 0;
 /// Result type containing 5 numeric values, one for each stake type.
 pub type StakeByTypeResult<BigUint> = MultiValue5<BigUint, BigUint, BigUint, BigUint, BigUint>;
 
+/// Result of paginated views: the id where the next page starts (0 if there are no more pages),
+/// followed by the items of the current page.
+pub type PageResult<M, T> = MultiValue2<usize, MultiValueEncoded<M, T>>;
+
 #[multiversx_sc::derive::module]
 pub trait FundViewModule: fund_module::FundModule + user_data::UserDataModule {
     // UTILS
@@ -185,6 +189,28 @@ pub trait FundViewModule: fund_module::FundModule + user_data::UserDataModule {
         result
     }
 
+    /// Paginated version of `getAllUserStakeByType`. Pages start with a user id.
+    #[view(getAllUserStakeByTypePage)]
+    fn get_all_user_stake_by_type_page(
+        &self,
+        start_id: usize,
+        limit: usize,
+    ) -> PageResult<Self::Api, MultiValue2<ManagedAddress, StakeByTypeResult<BigUint>>> {
+        let (user_ids, next_id) = self.user_id_page(start_id, limit);
+        let mut result = MultiValueEncoded::new();
+        for user_id in user_ids {
+            result.push(
+                (
+                    self.get_user_address(user_id),
+                    self.get_user_stake_by_type(user_id),
+                )
+                    .into(),
+            );
+        }
+
+        (next_id, result).into()
+    }
+
     // DEFERRED PAYMENT BREAKDOWN
 
     #[view(getUserDeferredPaymentList)]
@@ -242,6 +268,28 @@ pub trait FundViewModule: fund_module::FundModule + user_data::UserDataModule {
         result
     }
 
+    /// Paginated version of `getFullWaitingList`. Pages start with a fund item id.
+    #[view(getWaitingListPage)]
+    fn get_waiting_list_page(
+        &self,
+        start_id: usize,
+        limit: usize,
+    ) -> PageResult<Self::Api, MultiValue3<ManagedAddress, BigUint, u64>> {
+        let mut result = MultiValueEncoded::new();
+        let next_id =
+            self.foreach_fund_by_type_page(FundType::Waiting, start_id, limit, |fund_item| {
+                if let FundDescription::Waiting { created } = fund_item.fund_desc {
+                    let user_address = self.get_user_address(fund_item.user_id);
+                    result.push(MultiValue3::from((
+                        user_address,
+                        fund_item.balance,
+                        created,
+                    )));
+                }
+            });
+        (next_id, result).into()
+    }
+
     // FULL ACTIVE LIST
 
     #[view(getFullActiveList)]
@@ -262,4 +310,27 @@ pub trait FundViewModule: fund_module::FundModule + user_data::UserDataModule {
         });
         result
     }
+
+    /// Paginated version of `getFullActiveList`. Pages start with a fund item id.
+    #[view(getActiveListPage)]
+    fn get_active_list_page(
+        &self,
+        start_id: usize,
+        limit: usize,
+    ) -> PageResult<Self::Api, MultiValue2<ManagedAddress, BigUint>> {
+        let mut result = MultiValueEncoded::new();
+        let next_id =
+            self.foreach_fund_by_type_page(FundType::Active, start_id, limit, |fund_item| {
+                if self.is_empty_user_address(fund_item.user_id) {
+                    result.push(MultiValue2::from((
+                        ManagedAddress::zero(),
+                        fund_item.balance,
+                    )));
+                } else {
+                    let user_address = self.get_user_address(fund_item.user_id);
+                    result.push(MultiValue2::from((user_address, fund_item.balance)));
+                }
+            });
+        (next_id, result).into()
+    }
 }
@@ -33,7 +33,9 @@ pub enum FundDescription {
 }
 
 /// Same as fund description, but only the enum with no additional data.
-#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, PartialEq, Clone, Copy, Debug)]
+#[derive(
+    TopEncode, TopDecode, NestedEncode, NestedDecode, TypeAbi, PartialEq, Clone, Copy, Debug,
+)]
 pub enum FundType {
     /// Funds that can only be extracted from contract. Will never be used as stake.
     WithdrawOnly,
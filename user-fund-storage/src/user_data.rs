@@ -1,3 +1,5 @@
+use core::ops::RangeInclusive;
+
 multiversx_sc::imports!();
 
 /// Deals with storage of data about delegators.
@@ -92,4 +94,34 @@ pub trait UserDataModule {
         }
         result
     }
+
+    /// Splits off the user ids starting with `start_id` that fit in a page of size `limit`.
+    /// Also yields the id where the next page starts, or 0 if there is no next page.
+    /// User ids start from 1, so a `start_id` of 0 is the same as 1.
+    fn user_id_page(&self, start_id: usize, limit: usize) -> (RangeInclusive<usize>, usize) {
+        require!(limit > 0, "page limit must be positive");
+        let num_users = self.get_num_users();
+        let first_id = core::cmp::max(start_id, 1);
+        let last_id = core::cmp::min(first_id.saturating_add(limit - 1), num_users);
+        let next_id = if last_id < num_users { last_id + 1 } else { 0 };
+        (first_id..=last_id, next_id)
+    }
+
+    /// Paginated version of `userIdsWithoutAddress`.
+    /// Yields the start id of the next page (0 if this was the last one), followed by the user ids found.
+    #[view(userIdsWithoutAddressPage)]
+    fn user_ids_without_address_page(
+        &self,
+        start_id: usize,
+        limit: usize,
+    ) -> MultiValue2<usize, MultiValueEncoded<usize>> {
+        let (user_ids, next_id) = self.user_id_page(start_id, limit);
+        let mut result = MultiValueEncoded::<_, usize>::new();
+        for user_id in user_ids {
+            if self.is_empty_user_address(user_id) {
+                result.push(user_id);
+            }
+        }
+        (next_id, result).into()
+    }
 }
@@ -220,3 +220,108 @@ fn test_full_cycle_1() {
         module.count_fund_items_by_user_type(user_id, FundType::WithdrawOnly, |_| true)
     );
 }
+
+#[test]
+fn test_transfer_active() {
+    let _ = DebugApi::dummy();
+    let module = user_fund_storage::fund_transf_module::contract_obj::<DebugApi>();
+
+    let from_user_id = 1;
+    let to_user_id = 2;
+
+    module.create_waiting(from_user_id, 3000u32.into());
+    module.create_waiting(from_user_id, 2000u32.into());
+
+    let mut amount = BigUint::from(5000u32);
+    let _ = module.swap_waiting_to_active(&mut amount, || false);
+    assert_eq!(amount, BigUint::zero());
+
+    // consumes the last entry completely, then part of the first one
+    let mut amount = BigUint::from(2500u32);
+    module.transfer_user_active(from_user_id, to_user_id, &mut amount);
+    assert_eq!(amount, BigUint::zero());
+
+    fund_module_check::check_consistency(&module, 3);
+    assert_eq!(
+        BigUint::from(5000u32),
+        module.query_sum_funds_by_type(FundType::Active, |_, _| true)
+    );
+    assert_eq!(
+        3,
+        module.count_fund_items_by_type(FundType::Active, |_| true)
+    );
+    assert_eq!(
+        BigUint::from(2500u32),
+        module.query_sum_funds_by_user_type(from_user_id, FundType::Active, |_| true)
+    );
+    assert_eq!(
+        1,
+        module.count_fund_items_by_user_type(from_user_id, FundType::Active, |_| true)
+    );
+    assert_eq!(
+        BigUint::from(2500u32),
+        module.query_sum_funds_by_user_type(to_user_id, FundType::Active, |_| true)
+    );
+    assert_eq!(
+        2,
+        module.count_fund_items_by_user_type(to_user_id, FundType::Active, |_| true)
+    );
+
+    // more than available: transfers everything that is left
+    let mut amount = BigUint::from(3000u32);
+    module.transfer_user_active(from_user_id, to_user_id, &mut amount);
+    assert_eq!(amount, BigUint::from(500u32));
+
+    fund_module_check::check_consistency(&module, 3);
+    assert_eq!(
+        BigUint::from(0u32),
+        module.query_sum_funds_by_user_type(from_user_id, FundType::Active, |_| true)
+    );
+    assert_eq!(
+        BigUint::from(5000u32),
+        module.query_sum_funds_by_user_type(to_user_id, FundType::Active, |_| true)
+    );
+}
+
+#[test]
+fn test_swap_waiting_to_active_largest_first() {
+    let _ = DebugApi::dummy();
+    let module = user_fund_storage::fund_transf_module::contract_obj::<DebugApi>();
+
+    // more entries than fit in one batch, each user with a different amount
+    let num_users = 30usize;
+    for user_id in 1..=num_users {
+        module.create_waiting(user_id, BigUint::from(user_id * 10));
+    }
+
+    // the 25 largest entries, plus a little of the 26th
+    let mut amount = BigUint::from(4503u32);
+    let mut swapped_users = Vec::new();
+    module.swap_waiting_to_active_largest_first(
+        &mut amount,
+        |user_id| swapped_users.push(user_id),
+        || false,
+    );
+    assert_eq!(amount, BigUint::zero());
+    assert_eq!(swapped_users, (5..=num_users).rev().collect::<Vec<usize>>());
+
+    fund_module_check::check_consistency(&module, num_users + 1);
+    for user_id in 6..=num_users {
+        assert_eq!(
+            BigUint::from(user_id * 10),
+            module.query_sum_funds_by_user_type(user_id, FundType::Active, |_| true)
+        );
+    }
+    assert_eq!(
+        BigUint::from(3u32),
+        module.query_sum_funds_by_user_type(5, FundType::Active, |_| true)
+    );
+    assert_eq!(
+        BigUint::from(47u32),
+        module.query_sum_funds_by_user_type(5, FundType::Waiting, |_| true)
+    );
+    assert_eq!(
+        BigUint::from(147u32),
+        module.query_sum_funds_by_type(FundType::Waiting, |_, _| true)
+    );
+}
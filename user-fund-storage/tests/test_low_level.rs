@@ -2,6 +2,7 @@ use multiversx_sc::types::BigUint;
 use multiversx_sc_scenario::DebugApi;
 use user_fund_storage::fund_module::*;
 use user_fund_storage::types::{AffectedUserIdVec, FundDescription, FundType};
+use user_fund_storage::user_data::UserDataModule;
 
 mod fund_module_check;
 
@@ -580,3 +581,41 @@ fn test_user_swap(direction: SwapDirection) {
         fund_module.query_sum_funds_by_user_type(user_id_2, FundType::Waiting, |_| true)
     );
 }
+
+#[test]
+fn test_user_swap_without_address() {
+    let _ = DebugApi::dummy();
+    let fund_module = user_fund_storage::fund_module::contract_obj::<DebugApi>();
+    let user_id = 1;
+    assert!(fund_module.is_empty_user_address(user_id));
+
+    fund_module.increase_fund_balance(
+        user_id,
+        FundDescription::Waiting {
+            created: WAITING_CREATED,
+        },
+        1000u32.into(),
+    );
+
+    // logging the movement must not require an address entry
+    let mut amount = BigUint::from(400u32);
+    let swapped = fund_module.split_convert_max_by_user(
+        Some(&mut amount),
+        user_id,
+        FundType::Waiting,
+        SwapDirection::Forwards,
+        |_| Some(FundDescription::WithdrawOnly),
+        || false,
+    );
+    assert_eq!(swapped, BigUint::from(400u32));
+
+    fund_module_check::check_consistency(&fund_module, 2);
+    assert_eq!(
+        BigUint::from(400u32),
+        fund_module.query_sum_funds_by_user_type(user_id, FundType::WithdrawOnly, |_| true)
+    );
+    assert_eq!(
+        BigUint::from(600u32),
+        fund_module.query_sum_funds_by_user_type(user_id, FundType::Waiting, |_| true)
+    );
+}